@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+
+use palaxy_core::naming::NameTemplate;
+use palaxy_core::prelude::*;
+use palaxy_core::scheduler::ConvertLimits;
+use palaxy_core::{DuplicatePolicy, RenameOptions};
+use serde::de::DeserializeOwned;
+
+pub const USAGE: &str = "\
+Usage: palaxy-cli <COMMAND> <SOURCE> [OPTIONS]
+
+Commands:
+  analyze    Checks the source and recommends a bundle flag
+  bundle     Bundles the chapters of the source into volumes
+  convert    Bundles the source and converts every volume
+  library    Bundles and converts every series inside of the source directory
+  rename     Bundles the source and previews names in the VOLUME-CHAPTER convention
+
+Bundle options (bundle, convert, library):
+  --flag <FLAG>              NAME, IMAGE, FOLDER, PAGES or MANUAL [default: recommendation of analyze]
+  --template <TEMPLATE>      DASHED, LABELED, COMPACT, MIHON or a custom regex
+  --sensibility <PERCENT>    Sensibility of the image detection [default: 75]
+  --detectors <DETECTORS>    Comma separated COLOR, BLEED, SIZE, JUMP and BLANK [default: COLOR]
+  --page-target <PAGES>      Pages per volume for PAGES [default: 200]
+  --page-tolerance <PAGES>   Allowed deviation for PAGES [default: 10% of the target]
+  --sizes <SIZES>            Comma separated chapters per volume for MANUAL
+  --duplicates <POLICY>      Bundles one of several chapters with the same number: PAGES, RESOLUTION
+                             or comma separated scanlation groups in the order of preference [default: all]
+  --plan <FILE>              Imports the volumes from a JSON or CSV plan instead of bundling
+  --export <FILE>            Exports the volumes as JSON or CSV plan (by the extension)
+
+Convert options (convert, library):
+  --target <DIRECTORY>       Directory the volumes are written to (required)
+  --format <FORMAT>          CBZ, EPUB or PDF [default: CBZ]
+  --direction <DIRECTION>    LTR or RTL, only used by EPUB [default: LTR]
+  --create-directory         Writes the volumes into a new directory named after the series
+  --max-volumes <COUNT>      Volumes converted at the same time
+  --max-pages <COUNT>        Pages loaded at the same time
+  --memory <MEGABYTES>       Memory ceiling for the loaded pages
+
+Rename options (rename):
+  --apply                    Renames the chapters and pages instead of only previewing them
+  --undo                     Reverts the last applied renames of the source, without bundling
+  --skip-chapters            Keeps the names of the chapters
+  --skip-pages               Keeps the names of the pages
+
+General options:
+  --progress                 Prints the progress as JSON lines to stderr
+  -h, --help                 Prints this help
+";
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Command {
+    Analyze,
+    Bundle,
+    Convert,
+    Library,
+    Rename,
+}
+
+pub struct Args {
+    pub command: Command,
+    pub source: PathBuf,
+    pub flag: Option<BundleFlag>,
+    pub template: Option<NameTemplate>,
+    pub sensibility: Option<usize>,
+    pub detectors: Option<Vec<DetectorKind>>,
+    pub page_target: Option<usize>,
+    pub page_tolerance: Option<usize>,
+    pub sizes: Option<Vec<usize>>,
+    pub duplicates: Option<DuplicatePolicy>,
+    pub plan: Option<PathBuf>,
+    pub export: Option<PathBuf>,
+    pub target: Option<PathBuf>,
+    pub file_format: FileFormat,
+    pub direction: Direction,
+    pub create_directory: bool,
+    pub limits: ConvertLimits,
+    pub rename: RenameOptions,
+    pub apply: bool,
+    pub undo: bool,
+    pub progress: bool,
+}
+
+// Reuses the serde names of the core types, e.g. `NAME` for `BundleFlag::NAME`
+fn parse_enum<T: DeserializeOwned>(option: &str, value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.trim().to_uppercase()))
+        .map_err(|_| format!("Invalid value '{}' for {}", value, option))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid number '{}' for {}", value, option))
+}
+
+fn parse_list<T>(
+    option: &str,
+    value: &str,
+    parse: fn(&str, &str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    value.split(',').map(|item| parse(option, item)).collect()
+}
+
+impl Args {
+    pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut arguments = arguments.into_iter();
+        let mut positional = Vec::new();
+        let mut args = Args {
+            command: Command::Analyze,
+            source: PathBuf::new(),
+            flag: None,
+            template: None,
+            sensibility: None,
+            detectors: None,
+            page_target: None,
+            page_tolerance: None,
+            sizes: None,
+            duplicates: None,
+            plan: None,
+            export: None,
+            target: None,
+            file_format: FileFormat::CBZ,
+            direction: Direction::LTR,
+            create_directory: false,
+            limits: ConvertLimits::default(),
+            rename: RenameOptions::default(),
+            apply: false,
+            undo: false,
+            progress: false,
+        };
+
+        while let Some(argument) = arguments.next() {
+            if !argument.starts_with('-') {
+                positional.push(argument);
+                continue;
+            }
+
+            // Flags without a value
+            match argument.as_str() {
+                "-h" | "--help" => return Err(String::new()),
+                "--create-directory" => {
+                    args.create_directory = true;
+                    continue;
+                }
+                "--progress" => {
+                    args.progress = true;
+                    continue;
+                }
+                "--apply" => {
+                    args.apply = true;
+                    continue;
+                }
+                "--undo" => {
+                    args.undo = true;
+                    continue;
+                }
+                "--skip-chapters" => {
+                    args.rename.skip_chapters = true;
+                    continue;
+                }
+                "--skip-pages" => {
+                    args.rename.skip_pages = true;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let value = arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", argument))?;
+            let option = argument.as_str();
+
+            match option {
+                "--flag" => args.flag = Some(parse_enum(option, &value)?),
+                "--template" => {
+                    args.template = Some(
+                        parse_enum(option, &value).unwrap_or(NameTemplate::CUSTOM(value)),
+                    )
+                }
+                "--sensibility" => args.sensibility = Some(parse_number(option, &value)?),
+                "--detectors" => args.detectors = Some(parse_list(option, &value, parse_enum)?),
+                "--page-target" => args.page_target = Some(parse_number(option, &value)?),
+                "--page-tolerance" => args.page_tolerance = Some(parse_number(option, &value)?),
+                "--sizes" => args.sizes = Some(parse_list(option, &value, parse_number)?),
+                "--duplicates" => {
+                    args.duplicates = Some(match value.trim().to_uppercase().as_str() {
+                        "PAGES" => DuplicatePolicy::PAGES,
+                        "RESOLUTION" => DuplicatePolicy::RESOLUTION,
+                        _ => DuplicatePolicy::GROUPS(value.split(',').map(|group| group.trim().to_string()).collect()),
+                    })
+                }
+                "--plan" => args.plan = Some(PathBuf::from(value)),
+                "--export" => args.export = Some(PathBuf::from(value)),
+                "--target" => args.target = Some(PathBuf::from(value)),
+                "--format" => args.file_format = parse_enum(option, &value)?,
+                "--direction" => {
+                    args.direction = match value.to_uppercase().as_str() {
+                        "LTR" => Direction::LTR,
+                        "RTL" => Direction::RTL,
+                        _ => return Err(format!("Invalid value '{}' for {}", value, option)),
+                    }
+                }
+                "--max-volumes" => args.limits.volumes = Some(parse_number(option, &value)?),
+                "--max-pages" => args.limits.pages = Some(parse_number(option, &value)?),
+                "--memory" => args.limits.memory = Some(parse_number(option, &value)?),
+                _ => return Err(format!("Unknown option {}", option)),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+
+        args.command = match positional.next().as_deref() {
+            Some("analyze") => Command::Analyze,
+            Some("bundle") => Command::Bundle,
+            Some("convert") => Command::Convert,
+            Some("library") => Command::Library,
+            Some("rename") => Command::Rename,
+            Some(command) => return Err(format!("Unknown command {}", command)),
+            None => return Err("Missing command".to_string()),
+        };
+        args.source = positional
+            .next()
+            .map(PathBuf::from)
+            .ok_or_else(|| "Missing source".to_string())?;
+
+        if let Some(argument) = positional.next() {
+            return Err(format!("Unexpected argument {}", argument));
+        }
+
+        match args.command {
+            Command::Convert if args.target.is_none() => {
+                return Err("Missing --target for convert".to_string())
+            }
+            Command::Library if args.target.is_none() => {
+                return Err("Missing --target for library".to_string())
+            }
+            _ => {}
+        }
+
+        Ok(args)
+    }
+}
@@ -0,0 +1,198 @@
+use std::process::exit;
+use std::sync::Arc;
+
+use palaxy_core::prelude::*;
+use palaxy_core::{analyze, convert, plan, rename, ConvertOptions, Library, Plan, PlanFile, PlanOptions, Series};
+use serde::Serialize;
+
+use crate::args::{Args, Command, USAGE};
+
+mod args;
+
+// The results are printed as JSON to stdout, errors as `{"error": {"code": "...", "message": "...", ...}}` to stderr.
+// Exit codes: 0 on success, 1 if the process or a single volume failed and 2 for invalid arguments.
+#[tokio::main]
+async fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) if e.is_empty() => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+
+    // Ctrl+C stops the process like the cancel button of the app, so partial outputs are removed
+    let cancellation = CancellationToken::default();
+    let signal = cancellation.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            signal.cancel();
+        }
+    });
+
+    match run(args, cancellation).await {
+        Ok(success) => {
+            if !success {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", serde_json::json!({ "error": e }));
+            exit(1);
+        }
+    }
+}
+
+fn print<T: Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("results are always serializable")
+    );
+}
+
+async fn run(args: Args, cancellation: CancellationToken) -> Result<bool, Error> {
+    let mut series = Series::new(&args.source).with_cancellation(cancellation.clone());
+
+    if let Some(template) = &args.template {
+        series = series.with_name_template(template.clone());
+    }
+
+    if args.progress {
+        series = series.with_progress(Arc::new(|progress| {
+            eprintln!("{}", serde_json::to_string(&progress).unwrap_or_default());
+        }));
+    }
+
+    match args.command {
+        Command::Analyze => {
+            print(&analyze(&series).await?);
+            Ok(true)
+        }
+        Command::Bundle => {
+            print(&bundle(&mut series, &args).await?);
+            Ok(true)
+        }
+        Command::Convert => {
+            let plan = bundle(&mut series, &args).await?;
+            let conversion = convert(&series, &plan, &convert_options(&args)).await?;
+
+            print(&conversion);
+            Ok(conversion.failed == 0)
+        }
+        Command::Library => library(&args, cancellation).await,
+        Command::Rename if args.undo => {
+            print(&rename::undo(&series.source)?);
+            Ok(true)
+        }
+        Command::Rename => {
+            let plan = bundle(&mut series, &args).await?;
+            let renames = rename::propose(&series, &plan, &args.rename)?;
+
+            if args.apply {
+                renames.apply()?;
+            }
+
+            print(&renames);
+            Ok(true)
+        }
+    }
+}
+
+// An imported plan replaces bundling, exported are both imported and bundled plans
+async fn bundle(series: &mut Series, args: &Args) -> Result<Plan, Error> {
+    let plan = match &args.plan {
+        Some(path) => {
+            let import = PlanFile::read(path)?.import(series).await?;
+
+            if !import.omitted.is_empty() {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({ "warning": "Chapters are not part of the plan", "chapters": import.omitted })
+                );
+            }
+
+            import.plan
+        }
+        None => bundle_source(series, args).await?,
+    };
+
+    if let Some(path) = &args.export {
+        PlanFile::from_plan(&plan, &series.source)?.write(path)?;
+    }
+
+    Ok(plan)
+}
+
+// Without a given flag, the flag and naming convention recommended by `analyze` are used, just like in the app
+async fn bundle_source(series: &mut Series, args: &Args) -> Result<Plan, Error> {
+    let flag = match &args.flag {
+        Some(flag) => flag.clone(),
+        None => {
+            let analysis = analyze(series).await?;
+
+            if let (BundleFlag::NAME, Some(template)) = (&analysis.flag, analysis.template) {
+                series.name_template = template;
+            }
+
+            analysis.flag
+        }
+    };
+
+    plan(series, &plan_options(args, flag)).await
+}
+
+fn plan_options(args: &Args, flag: BundleFlag) -> PlanOptions {
+    PlanOptions {
+        flag,
+        sensibility: args.sensibility,
+        page_target: args.page_target,
+        page_tolerance: args.page_tolerance,
+        detectors: args.detectors.clone(),
+        sizes: args.sizes.clone(),
+        duplicates: args.duplicates.clone(),
+    }
+}
+
+fn convert_options(args: &Args) -> ConvertOptions {
+    ConvertOptions {
+        target: args.target.clone().unwrap_or_default(),
+        create_directory: args.create_directory,
+        file_format: args.file_format,
+        direction: args.direction,
+        limits: args.limits,
+    }
+}
+
+// Every series of the library uses its recommended flag, unless a flag is given for all of them
+async fn library(args: &Args, cancellation: CancellationToken) -> Result<bool, Error> {
+    let mut library = Library::new(&args.source).with_cancellation(cancellation);
+
+    if args.progress {
+        library = library.with_progress(Arc::new(|progress| {
+            eprintln!("{}", serde_json::to_string(&progress).unwrap_or_default());
+        }));
+    }
+
+    library.scan().await?;
+
+    for entry in library.entries.iter_mut() {
+        if let Some(template) = &args.template {
+            entry.name_template = template.clone();
+        }
+        if let Some(flag) = &args.flag {
+            entry.options = Some(plan_options(args, flag.clone()));
+        }
+    }
+
+    library
+        .prepare(&plan_options(args, BundleFlag::default()))
+        .await?;
+    let report = library.convert(&convert_options(args)).await?;
+
+    print(&report);
+    Ok(report.failed == 0)
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{copy, sink, BufRead, BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use image::{ImageDecoder, ImageReader};
+use sevenz_rust::{Password, SevenZReader};
+use zip::ZipArchive;
+
+use crate::prelude::*;
+
+// Archives are treated like chapter directories. Their pages are addressed by a "virtual" path,
+// which is the archive path joined with the entry name (e.g. `Chapter 01.cbz/001.jpg`).
+const ZIP_EXTENSIONS: [&str; 2] = ["zip", "cbz"];
+const SEVENZ_EXTENSIONS: [&str; 2] = ["7z", "cb7"];
+
+enum Kind {
+    Zip,
+    SevenZ,
+}
+
+fn kind(path: &Path) -> Option<Kind> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    if ZIP_EXTENSIONS.contains(&extension.as_str()) {
+        Some(Kind::Zip)
+    } else if SEVENZ_EXTENSIONS.contains(&extension.as_str()) {
+        Some(Kind::SevenZ)
+    } else {
+        None
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    kind(path).is_some() && path.is_file()
+}
+
+// Splits a virtual path into the archive it lives in and the name of the entry inside of it.
+pub fn split(path: &Path) -> Option<(PathBuf, String)> {
+    let archive = path.ancestors().skip(1).find(|ancestor| is_archive(ancestor))?;
+
+    let entry = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    Some((archive.to_path_buf(), entry))
+}
+
+// Returns the archive of a virtual path or the path itself if it is a regular file.
+pub fn container(path: &Path) -> PathBuf {
+    split(path)
+        .map(|(archive, _)| archive)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+// Pages live directly inside of their chapter directory or, as virtual path, inside of their archive
+pub fn chapter(page: &Path) -> Option<PathBuf> {
+    split(page)
+        .map(|(archive, _)| archive)
+        .or_else(|| page.parent().map(Path::to_path_buf))
+}
+
+pub fn is_file(path: &Path) -> bool {
+    path.is_file() || split(path).is_some()
+}
+
+// Collects the virtual paths of all supported images inside an archive.
+pub fn entries(archive: &PathBuf) -> Result<Vec<PathBuf>, Error> {
+    archive_entries(archive).map_err(|e| e.with_path(archive))
+}
+
+fn archive_entries(archive: &PathBuf) -> Result<Vec<PathBuf>, Error> {
+    let names: Vec<String> = match kind(archive) {
+        Some(Kind::Zip) => {
+            let zip = ZipArchive::new(File::open(archive)?)?;
+            zip.file_names().map(|name| name.to_string()).collect()
+        }
+        Some(Kind::SevenZ) => {
+            let reader = SevenZReader::open(archive, Password::empty())?;
+            reader
+                .archive()
+                .files
+                .iter()
+                .filter(|entry| !entry.is_directory())
+                .map(|entry| entry.name().to_string())
+                .collect()
+        }
+        None => return Err(Error::InvalidPath(archive.clone(), "Archive expected".to_string())),
+    };
+
+    Ok(names
+        .into_iter()
+        // exclude directories, hidden files and metadata folders like `__MACOSX`
+        .filter(|name| !name.ends_with('/'))
+        .filter(|name| {
+            !name
+                .split('/')
+                .any(|part| part.starts_with('.') || part.starts_with("__"))
+        })
+        .map(|name| archive.join(name))
+        .filter(|path| get_file_info(path).is_ok())
+        .collect())
+}
+
+// Reads a page either from the file system or, for virtual paths, out of its archive.
+// Use a `Reader` to read several pages of the same archive.
+pub fn read(path: &PathBuf) -> Result<Vec<u8>, Error> {
+    read_page(path).map_err(|e| e.with_path(path))
+}
+
+fn read_page(path: &PathBuf) -> Result<Vec<u8>, Error> {
+    let (archive, entry) = match split(path) {
+        Some(split) => split,
+        None => return Ok(std::fs::read(path)?),
+    };
+
+    match kind(&archive) {
+        Some(Kind::Zip) => read_zip(&mut ZipArchive::new(File::open(&archive)?)?, &entry),
+        Some(Kind::SevenZ) => read_sevenz(&archive, &entry),
+        None => unreachable!("split only returns supported archives"),
+    }
+}
+
+fn read_zip(zip: &mut ZipArchive<File>, entry: &str) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    zip.by_name(entry)?.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+// Decodes a 7z archive up to a single entry
+fn read_sevenz(archive: &Path, entry: &str) -> Result<Vec<u8>, Error> {
+    let mut reader = SevenZReader::open(archive, Password::empty())?;
+    let mut buffer = None;
+
+    reader.for_each_entries(|file, data| {
+        if file.name() != entry {
+            // Solid archives are decoded sequentially, so skipped entries still have to be drained
+            copy(data, &mut sink())?;
+            return Ok(true);
+        }
+
+        let mut entry_buffer = Vec::new();
+        data.read_to_end(&mut entry_buffer)?;
+        buffer = Some(entry_buffer);
+        Ok(false)
+    })?;
+
+    buffer.ok_or_else(|| Error::NotFound(format!("Entry '{}' in {:?}", entry, archive)))
+}
+
+// Decodes all pages of a 7z archive in a single pass
+fn read_sevenz_pages(archive: &Path) -> Result<HashMap<String, Vec<u8>>, Error> {
+    let mut reader = SevenZReader::open(archive, Password::empty())?;
+    let mut pages = HashMap::new();
+
+    reader.for_each_entries(|file, data| {
+        if file.is_directory() || get_file_info(&archive.join(file.name())).is_err() {
+            copy(data, &mut sink())?;
+            return Ok(true);
+        }
+
+        let mut buffer = Vec::new();
+        data.read_to_end(&mut buffer)?;
+        pages.insert(file.name().to_string(), buffer);
+        Ok(true)
+    })?;
+
+    Ok(pages)
+}
+
+enum Opened {
+    Zip(ZipArchive<File>),
+    // The decoded pages that were not read yet
+    SevenZ(HashMap<String, Vec<u8>>),
+}
+
+// Reads pages out of their archives, but opens every archive only once.
+// Zip archives are kept open, while 7z archives are decoded in a single pass on the first read,
+// as solid archives can only be decoded sequentially. Their pages are kept in memory until they are read,
+// so a page that is read twice is decoded on its own the second time.
+#[derive(Default)]
+pub struct Reader {
+    archives: Mutex<HashMap<PathBuf, Arc<Mutex<Option<Opened>>>>>,
+}
+
+impl Reader {
+    pub fn read(&self, path: &PathBuf) -> Result<Vec<u8>, Error> {
+        self.read_page(path).map_err(|e| e.with_path(path))
+    }
+
+    fn read_page(&self, path: &PathBuf) -> Result<Vec<u8>, Error> {
+        let (archive, entry) = match split(path) {
+            Some(split) => split,
+            None => return Ok(std::fs::read(path)?),
+        };
+
+        // Only the archive itself is locked while it is opened, so other archives can be read in the meantime
+        let opened = self
+            .archives
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(archive.clone())
+            .or_default()
+            .clone();
+        let mut opened = opened.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if opened.is_none() {
+            *opened = Some(match kind(&archive) {
+                Some(Kind::Zip) => Opened::Zip(ZipArchive::new(File::open(&archive)?)?),
+                Some(Kind::SevenZ) => Opened::SevenZ(read_sevenz_pages(&archive)?),
+                None => unreachable!("split only returns supported archives"),
+            });
+        }
+
+        match opened.as_mut() {
+            Some(Opened::Zip(zip)) => read_zip(zip, &entry),
+            Some(Opened::SevenZ(pages)) => match pages.remove(&entry) {
+                Some(buffer) => Ok(buffer),
+                None => read_sevenz(&archive, &entry),
+            },
+            None => unreachable!("the archive was opened above"),
+        }
+    }
+}
+
+// The size of the decoded pixels of every page, which is read from the header of the page.
+// The pages of an archive are read in a single pass.
+pub fn decoded_sizes(pages: &[PathBuf]) -> Result<Vec<u64>, Error> {
+    let mut sizes = vec![0; pages.len()];
+    let mut archives: HashMap<PathBuf, Vec<(usize, String)>> = HashMap::new();
+
+    for (i, page) in pages.iter().enumerate() {
+        match split(page) {
+            Some((archive, entry)) => archives.entry(archive).or_default().push((i, entry)),
+            None => {
+                sizes[i] = File::open(page)
+                    .map_err(Error::from)
+                    .and_then(|file| decoded_size(BufReader::new(file)))
+                    .map_err(|e| e.with_path(page))?;
+            }
+        }
+    }
+
+    for (archive, entries) in archives {
+        archive_decoded_sizes(&archive, &entries, &mut sizes).map_err(|e| e.with_path(&archive))?;
+    }
+
+    Ok(sizes)
+}
+
+fn decoded_size<R: BufRead + Seek>(reader: R) -> Result<u64, Error> {
+    let decoder = ImageReader::new(reader).with_guessed_format()?.into_decoder()?;
+    Ok(decoder.total_bytes())
+}
+
+fn archive_decoded_sizes(archive: &Path, entries: &[(usize, String)], sizes: &mut [u64]) -> Result<(), Error> {
+    match kind(archive) {
+        Some(Kind::Zip) => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+
+            for (i, entry) in entries {
+                sizes[*i] = decoded_size(Cursor::new(read_zip(&mut zip, entry)?))?;
+            }
+        }
+        Some(Kind::SevenZ) => {
+            let mut reader = SevenZReader::open(archive, Password::empty())?;
+            let mut remaining = entries.len();
+            let mut failure = None;
+
+            reader.for_each_entries(|file, data| {
+                let indices: Vec<usize> = entries
+                    .iter()
+                    .filter(|(_, entry)| entry == file.name())
+                    .map(|(i, _)| *i)
+                    .collect();
+
+                if indices.is_empty() {
+                    copy(data, &mut sink())?;
+                    return Ok(true);
+                }
+
+                let mut buffer = Vec::new();
+                data.read_to_end(&mut buffer)?;
+
+                match decoded_size(Cursor::new(buffer)) {
+                    Ok(size) => indices.iter().for_each(|&i| sizes[i] = size),
+                    Err(e) => {
+                        failure = Some(e);
+                        return Ok(false);
+                    }
+                }
+
+                // The remaining entries do not have to be decoded once all pages are found
+                remaining -= indices.len();
+                Ok(remaining > 0)
+            })?;
+
+            if let Some(e) = failure {
+                return Err(e);
+            }
+        }
+        None => return Err(Error::InvalidPath(archive.to_path_buf(), "Archive expected".to_string())),
+    }
+
+    Ok(())
+}
+
+// Returns the uncompressed size of every page without reading it.
+// Every archive is only opened once to read the sizes of its entries.
+pub fn sizes(pages: &[PathBuf]) -> Result<Vec<u64>, Error> {
+    let mut sizes = vec![0; pages.len()];
+    let mut archives: HashMap<PathBuf, Vec<(usize, String)>> = HashMap::new();
+
+    for (i, page) in pages.iter().enumerate() {
+        match split(page) {
+            Some((archive, entry)) => archives.entry(archive).or_default().push((i, entry)),
+            None => sizes[i] = page.metadata().map_err(|e| Error::from(e).with_path(page))?.len(),
+        }
+    }
+
+    for (archive, entries) in archives {
+        archive_sizes(&archive, &entries, &mut sizes).map_err(|e| e.with_path(&archive))?;
+    }
+
+    Ok(sizes)
+}
+
+fn archive_sizes(archive: &Path, entries: &[(usize, String)], sizes: &mut [u64]) -> Result<(), Error> {
+    match kind(archive) {
+        Some(Kind::Zip) => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+
+            for (i, entry) in entries {
+                sizes[*i] = zip.by_name(entry)?.size();
+            }
+        }
+        Some(Kind::SevenZ) => {
+            let reader = SevenZReader::open(archive, Password::empty())?;
+            let files: HashMap<&str, u64> = reader
+                .archive()
+                .files
+                .iter()
+                .map(|file| (file.name(), file.size()))
+                .collect();
+
+            for (i, entry) in entries {
+                sizes[*i] = *files
+                    .get(entry.as_str())
+                    .ok_or_else(|| Error::NotFound(format!("Entry '{}' in {:?}", entry, archive)))?;
+            }
+        }
+        None => return Err(Error::InvalidPath(archive.to_path_buf(), "Archive expected".to_string())),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,810 @@
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use image::DynamicImage;
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use regex::Regex;
+use tokio::fs::{read_dir, ReadDir};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+use crate::archive;
+use crate::prelude::*;
+
+pub struct Collector {
+    base_directory: PathBuf,
+    progress: Option<ProgressReporter>,
+    cancellation: CancellationToken,
+}
+
+lazy_static! {
+    static ref RE: Regex = Regex::new(r"(\d+(?:\.\d+)?)|([^\W\d_]+)").unwrap();
+    static ref RE_BRACKETS: Regex = Regex::new(r"\[[^\]]*\]|\([^)]*\)|\{[^}]*\}").unwrap();
+}
+
+// Sorts the collected paths, e.g. `Collector::sort_natural`
+pub type Comparator = &'static (dyn Fn(&PathBuf, &PathBuf) -> Ordering + Sync);
+
+// A single part of a natural sort key, see `Collector::natural_key`
+#[derive(Debug, Clone, PartialEq)]
+pub enum NaturalSegment {
+    Number(f64),
+    Text(String),
+}
+
+impl Collector {
+    pub fn new(base_directory: &PathBuf) -> Self {
+        Self {
+            base_directory: base_directory.clone(),
+            progress: None,
+            cancellation: CancellationToken::default(),
+        }
+    }
+
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    // Creates a function that reports every finished item of a stage. It can be shared between tasks.
+    fn reporter(&self, stage: Stage, total: usize) -> impl Fn(&PathBuf) + Clone + Send + Sync + 'static {
+        let progress = self.progress.clone();
+        let done = Arc::new(AtomicUsize::new(0));
+
+        move |file: &PathBuf| {
+            let index = done.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+
+            if let Some(progress) = &progress {
+                progress(Progress {
+                    stage,
+                    series: None,
+                    volume: None,
+                    index,
+                    total,
+                    file: Some(file.clone()),
+                });
+            }
+        }
+    }
+
+    pub async fn collect_chapters(
+        &mut self,
+        comparator: Option<Comparator>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut chapters = Self::collect(&self.base_directory, true).await?;
+
+        if let Some(comparator) = comparator {
+            chapters.par_sort_by(comparator);
+        }
+
+        Ok(chapters)
+    }
+
+    // Collects the chapters of a nested `Series/Volume/Chapter` layout, grouped by their volume directory.
+    pub async fn collect_volumes(
+        &self,
+        comparator: Option<Comparator>,
+    ) -> Result<Vec<Vec<PathBuf>>, Error> {
+        let mut volume_dirs = Self::collect(&self.base_directory, true).await?;
+
+        if let Some(comparator) = comparator {
+            volume_dirs.par_sort_by(comparator);
+        }
+
+        let mut volumes = Vec::with_capacity(volume_dirs.len());
+
+        for volume_dir in volume_dirs {
+            self.cancellation.check()?;
+
+            if !volume_dir.is_dir() {
+                return Err(Error::InvalidPath(volume_dir, "Volume directory expected".to_string()));
+            }
+
+            let mut chapters = Self::collect(&volume_dir, true).await?;
+
+            if let Some(comparator) = comparator {
+                chapters.par_sort_by(comparator);
+            }
+
+            volumes.push(chapters);
+        }
+
+        Ok(volumes)
+    }
+
+    pub async fn collect_pages(
+        &self,
+        chapters: Vec<PathBuf>,
+        comparator: Option<Comparator>,
+    ) -> Result<Vec<Vec<PathBuf>>, Error> {
+        let mut pages = Vec::with_capacity(chapters.len());
+        let report = self.reporter(Stage::COLLECT, chapters.len());
+        // The tasks are spawned from the rayon threads, which are not part of the runtime
+        let runtime = Handle::current();
+
+        let handles: Vec<JoinHandle<Result<(usize, Vec<PathBuf>), Error>>> = chapters
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, chapter_dir)| {
+                let report = report.clone();
+                let cancellation = self.cancellation.clone();
+
+                runtime.spawn(async move {
+                    cancellation.check()?;
+
+                    // Archives are read in place instead of being unpacked first
+                    let mut chapter_images = if archive::is_archive(&chapter_dir) {
+                        archive::entries(&chapter_dir)
+                    } else {
+                        Self::collect(&chapter_dir, false).await
+                    }
+                    .map_err(|e| e.with_chapter(index + 1))?;
+
+                    if let Some(comparator) = comparator {
+                        chapter_images.par_sort_by(comparator);
+                    }
+
+                    report(&chapter_dir);
+
+                    Ok((index, chapter_images))
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            match handle.await {
+                Ok(Ok((i, chapter_images))) => pages.insert(i, chapter_images),
+                Ok(Err(e)) => return Err(e),
+                Err(e) => return Err(Error::AsyncTaskError(e.to_string())),
+            }
+        }
+
+        Ok(pages)
+    }
+
+    pub async fn determine_volume_start_chapters(
+        &self,
+        chapters: Vec<PathBuf>,
+        images_per_chapter: Vec<Vec<PathBuf>>,
+        sensibility: f64,
+        detectors: Vec<DetectorKind>,
+    ) -> Result<Vec<CoverScore>, Error> {
+        let detectors: Vec<Box<dyn BoundaryDetector>> =
+            detectors.into_iter().map(Self::detector).collect();
+        let total_weight: f64 = detectors.iter().map(|detector| detector.weight()).sum();
+
+        if total_weight <= 0.0 {
+            return Err(Error::Unsupported("Bundling without any detector".to_string()));
+        }
+
+        // The higher the sensibility, the lower the score of a volume start has to be.
+        // With only the color detector, the score is the colorfulness of the cover.
+        let threshold = 1.0 - sensibility;
+
+        let report = self.reporter(Stage::DETECT, chapters.len());
+        let runtime = Handle::current();
+        // The last page is only decoded for the blank page detector
+        let needs_last_page = detectors.iter().any(|detector| detector.kind() == DetectorKind::BLANK);
+
+        // Load the pages needed by the detectors in parallel, the scoring itself is cheap
+        let handles: Vec<JoinHandle<Result<ChapterSample, Error>>> = chapters
+            .into_par_iter()
+            .zip(images_per_chapter.into_par_iter())
+            .enumerate()
+            .map(|(i, (chapter, images))| {
+                let report = report.clone();
+                let cancellation = self.cancellation.clone();
+
+                runtime.spawn_blocking(move || {
+                    cancellation.check()?;
+
+                    // Both pages are read out of the same archive, which is only opened once
+                    let reader = archive::Reader::default();
+                    let load = |path: &PathBuf| {
+                        reader
+                            .read(path)
+                            .and_then(|data| image::load_from_memory(&data).map_err(|e| Error::from(e).with_path(path)))
+                            .map_err(|e| e.with_chapter(i + 1))
+                    };
+
+                    let (cover_path, last_path) = match (images.first(), images.last()) {
+                        (Some(first), Some(last)) => (first.clone(), last.clone()),
+                        _ => {
+                            return Err(
+                                Error::InvalidPath(chapter, "Chapter contains no pages".to_string()).with_chapter(i + 1)
+                            )
+                        }
+                    };
+
+                    let cover_image = load(&cover_path)?;
+                    let last_image = match (needs_last_page, last_path == cover_path) {
+                        (false, _) => None,
+                        (true, true) => Some(cover_image.thumbnail(256, 256)),
+                        (true, false) => Some(load(&last_path)?.thumbnail(256, 256)),
+                    };
+
+                    report(&chapter);
+
+                    Ok(ChapterSample {
+                        index: i,
+                        number: Self::chapter_number(&chapter),
+                        cover: cover_path,
+                        page_size: (cover_image.width(), cover_image.height()),
+                        first_page: cover_image.thumbnail(256, 256),
+                        colorfulness: Self::colorfulness(&cover_image),
+                        last_page: last_image,
+                    })
+                })
+            })
+            .collect();
+
+        let mut samples: Vec<ChapterSample> = Vec::with_capacity(handles.len());
+        for handle in handles {
+            samples.push(handle.await.map_err(|e| Error::AsyncTaskError(e.to_string()))??);
+        }
+
+        let cover_scores = samples
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let previous = i.checked_sub(1).map(|i| &samples[i]);
+
+                let scores: Vec<DetectorScore> = detectors
+                    .iter()
+                    .map(|detector| DetectorScore {
+                        detector: detector.kind(),
+                        score: detector.score(sample, previous).clamp(0.0, 1.0),
+                    })
+                    .collect();
+
+                let color = scores
+                    .iter()
+                    .find(|score| score.detector == DetectorKind::COLOR)
+                    .map(|score| score.score);
+                let support = detectors
+                    .iter()
+                    .zip(scores.iter())
+                    .filter(|(detector, _)| detector.kind() != DetectorKind::COLOR)
+                    .map(|(detector, score)| detector.weight() * score.score)
+                    .sum::<f64>()
+                    / total_weight;
+
+                // The other detectors only raise the score of a colored cover by their share of the weight,
+                // so chapters without any color are never split off, no matter how many other detectors agree.
+                // Without the color detector, the score is the weighted mean of the enabled detectors.
+                let score = match color {
+                    Some(color) => (color * (1.0 + support)).min(1.0),
+                    None => support,
+                };
+
+                // The first chapter always starts a volume
+                let volume_start = previous.is_none() || score >= threshold;
+
+                // The confidence describes how far the score is away from the threshold,
+                // relative to the maximum possible distance on that side of the threshold.
+                let confidence = match (previous, volume_start) {
+                    (None, _) => 1.0,
+                    (_, true) => (score - threshold) / (1.0 - threshold).max(f64::EPSILON),
+                    (_, false) => (threshold - score) / threshold.max(f64::EPSILON),
+                };
+
+                CoverScore {
+                    chapter: sample.index,
+                    cover: sample.cover.clone(),
+                    colorfulness: sample.colorfulness,
+                    score,
+                    confidence: confidence.clamp(0.0, 1.0),
+                    volume_start,
+                    detectors: scores,
+                }
+            })
+            .collect();
+
+        Ok(cover_scores)
+    }
+
+    pub fn detector(kind: DetectorKind) -> Box<dyn BoundaryDetector> {
+        match kind {
+            DetectorKind::COLOR => Box::new(ColorCoverDetector),
+            DetectorKind::BLEED => Box::new(FullBleedDetector),
+            DetectorKind::SIZE => Box::new(PageSizeDetector),
+            DetectorKind::JUMP => Box::new(NumberJumpDetector),
+            DetectorKind::BLANK => Box::new(BlankPageDetector),
+        }
+    }
+
+    pub fn calculate_volume_sizes(
+        &self,
+        mut book_start_chapters: Vec<usize>,
+        total_chapters: usize,
+    ) -> Result<Vec<usize>, Error> {
+        let mut book_chapters: Vec<usize> = Vec::new();
+
+        // Remove the first chapter because it's always a book start
+        if book_start_chapters.len() > 0 {
+            book_start_chapters.remove(0);
+        } else {
+            return Err(Error::NotFound("No chapters found".to_string()));
+        }
+
+        let mut prev_chapter = 0;
+        for chapter in book_start_chapters {
+            book_chapters.push(chapter - prev_chapter);
+            prev_chapter = chapter;
+        }
+
+        // Add the remaining chapters.
+        book_chapters.push(total_chapters - prev_chapter);
+
+        Ok(book_chapters)
+    }
+
+    pub fn calculate_volume_sizes_by_pages(
+        &self,
+        images_per_chapter: &[Vec<PathBuf>],
+        target: usize,
+        tolerance: usize,
+    ) -> Result<Vec<usize>, Error> {
+        if target == 0 {
+            return Err(Error::Unsupported("A page target of 0 pages".to_string()));
+        }
+
+        let mut book_chapters: Vec<usize> = Vec::new();
+        let mut chapters = 0;
+        let mut pages = 0;
+
+        for images in images_per_chapter {
+            let next = pages + images.len();
+
+            // Chapters are never split. If the next chapter would overshoot the tolerance,
+            // the volume is closed early whenever that keeps it closer to the target.
+            if chapters > 0 && next > target + tolerance && target.abs_diff(pages) <= target.abs_diff(next) {
+                book_chapters.push(chapters);
+                chapters = 0;
+                pages = 0;
+            }
+
+            chapters += 1;
+            pages += images.len();
+
+            if pages >= target {
+                book_chapters.push(chapters);
+                chapters = 0;
+                pages = 0;
+            }
+        }
+
+        // Add the remaining chapters.
+        if chapters > 0 {
+            book_chapters.push(chapters);
+        }
+
+        Ok(book_chapters)
+    }
+
+    // Helper methods
+
+    // Measures how colorful an image is, ranging from 0 (grayscale) to 1 (very colorful).
+    // The measure is the colorfulness metric by Hasler and Süsstrunk, the spread plus 0.3 times the mean
+    // of the chroma in the opponent color channels (red-green and yellow-blue), so single colored covers count as well.
+    // Toned scans like sepia or slightly tinted paper only have chroma along the dominant hue of the page,
+    // which is why chroma along that hue is only counted beyond the strength of such a tint.
+    // The score is computed on a thumbnail, as the full resolution is not needed for the estimate.
+    pub fn colorfulness(img: &DynamicImage) -> f64 {
+        // Chroma per luminance of yellowed paper or sepia toning, which is not counted along the dominant hue.
+        // Toning scales with the brightness of a pixel, so a fixed allowance would count sepia midtones as color.
+        const TINT: f64 = 0.35;
+
+        let thumbnail = img.thumbnail(256, 256).to_rgb8();
+
+        // Red-green and yellow-blue channel and the luminance of every pixel
+        let (chroma, luminance): (Vec<[f64; 2]>, Vec<f64>) = thumbnail
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(f64::from);
+                ([r - g, 0.5 * (r + g) - b], 0.299 * r + 0.587 * g + 0.114 * b)
+            })
+            .unzip();
+
+        let mean = |samples: &[[f64; 2]]| {
+            let count = samples.len().max(1) as f64;
+            samples
+                .iter()
+                .fold([0f64; 2], |mean, sample| [mean[0] + sample[0] / count, mean[1] + sample[1] / count])
+        };
+
+        // The dominant hue is given by the mean chroma, which is the tint of the paper for toned scans
+        let dominant = mean(&chroma);
+        let strength = dominant[0].hypot(dominant[1]);
+        let (along, across) = match strength > f64::EPSILON {
+            true => ([dominant[0] / strength, dominant[1] / strength], [-dominant[1] / strength, dominant[0] / strength]),
+            false => ([1.0, 0.0], [0.0, 1.0]),
+        };
+
+        // The chroma split into the part along the dominant hue, reduced by the tint, and the part of all other hues
+        let chroma: Vec<[f64; 2]> = chroma
+            .iter()
+            .zip(&luminance)
+            .map(|(sample, luminance)| {
+                let hue = sample[0] * along[0] + sample[1] * along[1];
+                let other = sample[0] * across[0] + sample[1] * across[1];
+                [hue.signum() * (hue.abs() - TINT * luminance).max(0.0), other]
+            })
+            .collect();
+
+        let mean = mean(&chroma);
+        let count = chroma.len().max(1) as f64;
+        let variance = chroma.iter().fold(0.0, |variance, sample| {
+            variance + ((sample[0] - mean[0]).powi(2) + (sample[1] - mean[1]).powi(2)) / count
+        });
+
+        let colorfulness = variance.sqrt() + 0.3 * mean[0].hypot(mean[1]);
+
+        // A colorfulness of 50 is already a clearly colored image
+        (colorfulness / 50.0).min(1.0)
+    }
+
+    pub fn check_path<F>(paths: &Vec<PathBuf>, test_case: F) -> Result<Vec<PathBuf>, Error>
+    where
+        F: Fn(&PathBuf) -> bool,
+    {
+        let mut invalid_paths = Vec::new();
+
+        for path in paths {
+            if !test_case(path) {
+                invalid_paths.push(path.clone());
+            }
+        }
+
+        Ok(invalid_paths)
+    }
+
+    pub async fn collect(directory: &PathBuf, only_dirs: bool) -> Result<Vec<PathBuf>, Error> {
+        let mut entries: Vec<PathBuf> = Vec::new();
+        let mut paths: ReadDir = read_dir(directory)
+            .await
+            .map_err(|e| Error::from(e).with_path(directory))?;
+
+        while let Some(path) = paths
+            .next_entry()
+            .await
+            .map_err(|e| Error::from(e).with_path(directory))?
+        {
+            // exclude hidden files
+            if path.file_name().to_str().unwrap().starts_with(".") {
+                continue;
+            }
+            
+            // If only_dirs is true, we only want to collect directories (or archives, which are treated like directories)
+            // and raise an error if we find a file.
+            if only_dirs && !path.path().is_dir() && !archive::is_archive(&path.path()) {
+                return Err(Error::InvalidPath(path.path(), "Directory or archive expected".to_string()));
+            }
+            
+            // If only_dirs is false, we only want to collect files and raise an error if we find a directory.
+            if !only_dirs && path.path().is_dir() {
+                return Err(Error::InvalidPath(path.path(), "File expected".to_string()));
+            }
+
+            entries.push(path.path());
+        }
+
+        Ok(entries)
+    }
+
+    // Splits a file or directory name into its numbers and words, so that names can be compared naturally.
+    // Bracketed tags like scanlation groups are ignored, decimals are kept (chapter 12.5)
+    // and common prefixes like "Ch." or "Vol." are unified (or dropped if they lead the name).
+    pub fn natural_key(path: &PathBuf) -> Vec<NaturalSegment> {
+        let name = match get_file_info(path).is_ok() {
+            true => path.file_stem(),
+            false => Self::chapter_name(path).map(OsStr::new),
+        }
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+        let name = RE_BRACKETS.replace_all(&name, " ");
+
+        let mut key: Vec<NaturalSegment> = RE
+            .captures_iter(&name)
+            .filter_map(|capture| match (capture.get(1), capture.get(2)) {
+                (Some(number), _) => number.as_str().parse::<f64>().ok().map(NaturalSegment::Number),
+                (_, Some(word)) => Some(NaturalSegment::Text(
+                    match word.as_str() {
+                        "v" | "vol" | "volume" => "v",
+                        "c" | "ch" | "chap" | "chapter" => "c",
+                        "p" | "pg" | "page" => "p",
+                        other => other,
+                    }
+                    .to_string(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(NaturalSegment::Text(first)) = key.first() {
+            if ["v", "c", "p"].contains(&first.as_str()) {
+                key.remove(0);
+            }
+        }
+
+        key
+    }
+
+    // Returns the chapter number of a name, which is the number following a chapter prefix
+    // or the first number of the name otherwise.
+    pub fn chapter_number(path: &PathBuf) -> Option<f64> {
+        let key = Self::natural_key(path);
+
+        let prefixed = key.windows(2).find_map(|window| match window {
+            [NaturalSegment::Text(prefix), NaturalSegment::Number(number)] if prefix == "c" => Some(*number),
+            _ => None,
+        });
+
+        prefixed.or_else(|| {
+            key.iter().find_map(|segment| match segment {
+                NaturalSegment::Number(number) => Some(*number),
+                _ => None,
+            })
+        })
+    }
+
+    pub fn sort_natural(a: &PathBuf, b: &PathBuf) -> Ordering {
+        let an = Self::natural_key(a);
+        let bn = Self::natural_key(b);
+
+        let ordering = an
+            .iter()
+            .zip(bn.iter())
+            .map(|segments| match segments {
+                (NaturalSegment::Number(a), NaturalSegment::Number(b)) => {
+                    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+                }
+                (NaturalSegment::Text(a), NaturalSegment::Text(b)) => a.cmp(b),
+                // Numbers are always sorted before words
+                (NaturalSegment::Number(_), NaturalSegment::Text(_)) => Ordering::Less,
+                (NaturalSegment::Text(_), NaturalSegment::Number(_)) => Ordering::Greater,
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| an.len().cmp(&bn.len()));
+
+        // Fall back to the full name to keep the order deterministic for names with the same key
+        ordering.then_with(|| a.file_name().cmp(&b.file_name()))
+    }
+
+    // Returns the name of a chapter, which excludes the extension if the chapter is an archive.
+    pub fn chapter_name(path: &Path) -> Option<&str> {
+        if archive::is_archive(path) {
+            path.file_stem()?.to_str()
+        } else {
+            path.file_name()?.to_str()
+        }
+    }
+}
+
+// Standard deviation of the luminance of an image
+fn luminance_deviation(img: &DynamicImage) -> f64 {
+    let luma = img.to_luma8();
+    let count = luma.pixels().len().max(1) as f64;
+
+    let mean = luma.pixels().map(|pixel| pixel.0[0] as f64).sum::<f64>() / count;
+    let variance = luma
+        .pixels()
+        .map(|pixel| (pixel.0[0] as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+
+    variance.sqrt()
+}
+
+// Everything a boundary detector needs to know about a chapter
+pub struct ChapterSample {
+    pub index: usize,
+    pub number: Option<f64>,
+    pub cover: PathBuf,
+    // Size of the first page in full resolution
+    pub page_size: (u32, u32),
+    // Thumbnails of the first and last page
+    pub first_page: DynamicImage,
+    // The colorfulness of the first page, which is reported along with the scores of the detectors
+    pub colorfulness: f64,
+    // Only loaded if a detector needs it
+    pub last_page: Option<DynamicImage>,
+}
+
+// A strategy that rates how likely a chapter starts a new volume.
+// The scores of all detectors are combined by their weight to a single decision.
+pub trait BoundaryDetector: Send + Sync {
+    fn kind(&self) -> DetectorKind;
+
+    fn weight(&self) -> f64;
+
+    // Returns a score between 0 (no volume start) and 1 (certain volume start)
+    fn score(&self, current: &ChapterSample, previous: Option<&ChapterSample>) -> f64;
+}
+
+// Volumes usually start with a colored cover, while chapters are grayscale
+pub struct ColorCoverDetector;
+
+impl BoundaryDetector for ColorCoverDetector {
+    fn kind(&self) -> DetectorKind {
+        DetectorKind::COLOR
+    }
+
+    fn weight(&self) -> f64 {
+        0.5
+    }
+
+    fn score(&self, current: &ChapterSample, _previous: Option<&ChapterSample>) -> f64 {
+        current.colorfulness
+    }
+}
+
+// Title pages are printed without the white margins of regular pages
+pub struct FullBleedDetector;
+
+impl BoundaryDetector for FullBleedDetector {
+    fn kind(&self) -> DetectorKind {
+        DetectorKind::BLEED
+    }
+
+    fn weight(&self) -> f64 {
+        0.15
+    }
+
+    fn score(&self, current: &ChapterSample, _previous: Option<&ChapterSample>) -> f64 {
+        let luma = current.first_page.to_luma8();
+        let (width, height) = luma.dimensions();
+        let border = (width.min(height) / 32).max(1);
+
+        // Share of the pixels along the border that are not (almost) white paper
+        let (mut inked, mut total) = (0usize, 0usize);
+        for (x, y, pixel) in luma.enumerate_pixels() {
+            if x < border || y < border || x >= width - border || y >= height - border {
+                total += 1;
+                if pixel.0[0] < 230 {
+                    inked += 1;
+                }
+            }
+        }
+
+        inked as f64 / total.max(1) as f64
+    }
+}
+
+// Volumes can be scanned from a different source than the previous one, changing the page size
+pub struct PageSizeDetector;
+
+impl BoundaryDetector for PageSizeDetector {
+    fn kind(&self) -> DetectorKind {
+        DetectorKind::SIZE
+    }
+
+    fn weight(&self) -> f64 {
+        0.1
+    }
+
+    fn score(&self, current: &ChapterSample, previous: Option<&ChapterSample>) -> f64 {
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return 0.0,
+        };
+
+        let area = |(width, height): (u32, u32)| width as f64 * height as f64;
+        let (current_area, previous_area) = (area(current.page_size), area(previous.page_size));
+
+        // A change of 25% of the page area counts as a certain change
+        let change = (current_area - previous_area).abs() / current_area.max(previous_area).max(1.0);
+        change / 0.25
+    }
+}
+
+// Skipped chapter numbers or a numbering that starts over hint at a new volume
+pub struct NumberJumpDetector;
+
+impl BoundaryDetector for NumberJumpDetector {
+    fn kind(&self) -> DetectorKind {
+        DetectorKind::JUMP
+    }
+
+    fn weight(&self) -> f64 {
+        0.1
+    }
+
+    fn score(&self, current: &ChapterSample, previous: Option<&ChapterSample>) -> f64 {
+        match (current.number, previous.and_then(|previous| previous.number)) {
+            (Some(current), Some(previous)) if current < previous => 1.0,
+            // Decimal chapters (e.g. 12.5) are no jump, a gap of 5 chapters is a certain jump
+            (Some(current), Some(previous)) => (current - previous - 1.0) / 4.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// Volumes often end with a blank page, which is kept in the last chapter of the volume
+pub struct BlankPageDetector;
+
+impl BoundaryDetector for BlankPageDetector {
+    fn kind(&self) -> DetectorKind {
+        DetectorKind::BLANK
+    }
+
+    fn weight(&self) -> f64 {
+        0.15
+    }
+
+    fn score(&self, _current: &ChapterSample, previous: Option<&ChapterSample>) -> f64 {
+        let last_page = match previous.and_then(|previous| previous.last_page.as_ref()) {
+            Some(last_page) => last_page,
+            None => return 0.0,
+        };
+
+        // A blank page barely varies in its brightness
+        1.0 - luminance_deviation(last_page) / 10.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut paths: Vec<PathBuf> = names.iter().map(PathBuf::from).collect();
+        paths.sort_by(Collector::sort_natural);
+        paths.iter().map(|path| path.display().to_string()).collect()
+    }
+
+    fn chapters(pages: &[usize]) -> Vec<Vec<PathBuf>> {
+        pages.iter().map(|count| vec![PathBuf::from("1.png"); *count]).collect()
+    }
+
+    #[test]
+    fn natural_key_unifies_prefixes_and_ignores_tags() {
+        use NaturalSegment::*;
+
+        assert_eq!(
+            Collector::natural_key(&PathBuf::from("Vol.02 Chapter 012.5 [Group]")),
+            vec![Number(2.0), Text("c".to_string()), Number(12.5)]
+        );
+        assert_eq!(Collector::natural_key(&PathBuf::from("Ch.3 (v2)")), vec![Number(3.0)]);
+        assert_eq!(
+            Collector::natural_key(&PathBuf::from("c012_p014.jpg")),
+            vec![Number(12.0), Text("p".to_string()), Number(14.0)]
+        );
+    }
+
+    #[test]
+    fn sort_natural_orders_numbers_by_value() {
+        assert_eq!(
+            sorted(&["c10", "c2", "c1.5", "Chapter 1", "extra"]),
+            vec!["Chapter 1", "c1.5", "c2", "c10", "extra"]
+        );
+        assert_eq!(sorted(&["10.png", "9.png", "009.jpg"]), vec!["009.jpg", "9.png", "10.png"]);
+    }
+
+    #[test]
+    fn volume_sizes_by_pages_never_split_chapters() {
+        let collector = Collector::new(&PathBuf::new());
+        let sizes = |pages: &[usize], target, tolerance| {
+            collector
+                .calculate_volume_sizes_by_pages(&chapters(pages), target, tolerance)
+                .unwrap()
+        };
+
+        assert_eq!(sizes(&[4, 4, 4, 4, 4], 10, 2), vec![3, 2]);
+        // Closing the volume early keeps it closer to the target than overshooting
+        assert_eq!(sizes(&[8, 7, 3], 10, 1), vec![1, 2]);
+        // A chapter beyond the tolerance is still added if the volume would end up further away otherwise
+        assert_eq!(sizes(&[6, 7], 10, 1), vec![2]);
+        assert!(collector.calculate_volume_sizes_by_pages(&chapters(&[1]), 0, 0).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir, remove_file};
+use tokio::task::{spawn_blocking, JoinHandle};
+
+use crate::generator::{cbz, epub, partial_path, pdf, PageProgress};
+use crate::plan::Plan;
+use crate::prelude::*;
+use crate::scheduler::{ConvertLimits, Footprint, Scheduler};
+use crate::series::Series;
+
+// Where and how the volumes of a plan are written
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ConvertOptions {
+    pub target: PathBuf,
+    // Writes the volumes into a new directory named after the series inside of the target
+    pub create_directory: bool,
+    pub file_format: FileFormat,
+    pub direction: Direction,
+    pub limits: ConvertLimits,
+}
+
+// The result of every volume. Volumes may fail on their own without failing the whole conversion.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Conversion {
+    pub failed: usize,
+    pub volumes: Vec<VolumeReport>,
+}
+
+struct SharedData {
+    name: String,
+    target_directory: String,
+    pages: Vec<Vec<PathBuf>>,
+    chapters_per_volume: Vec<usize>,
+    progress: Option<ProgressReporter>,
+    cancellation: CancellationToken,
+}
+
+pub async fn convert(series: &Series, plan: &Plan, options: &ConvertOptions) -> Result<Conversion, Error> {
+    plan.check()?;
+
+    let target_directory_path = match options.create_directory {
+        true => {
+            let path = options.target.join(&series.name);
+            if !path.exists() {
+                create_dir(&path).await?;
+            }
+            Ok(path)
+        }
+        false => {
+            if !options.target.exists() {
+                Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Target directory does not exist",
+                ))
+                .with_path(&options.target))
+            } else {
+                Ok(options.target.clone())
+            }
+        }
+    }?
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let data = Arc::new(SharedData {
+        name: series.name.clone(),
+        target_directory: target_directory_path,
+        pages: plan.pages.clone(),
+        chapters_per_volume: plan.volume_sizes.clone(),
+        progress: series.progress(),
+        cancellation: series.cancellation().clone(),
+    });
+
+    let file_format = options.file_format;
+    let direction = options.direction;
+    let scheduler = Scheduler::new(options.limits);
+    let mut handles: Vec<JoinHandle<Result<PathBuf, Error>>> = Vec::with_capacity(plan.volume_sizes.len());
+
+    // Volumes are only started once the scheduler has a free slot for them,
+    // while their pages are processed in parallel within the shared page budget
+    for (i, chapters) in plan.volume_sizes.clone().into_iter().enumerate() {
+        // After a cancellation no further volume is started, neither before nor after waiting for a slot.
+        // The running volumes are still awaited below, so that their partial files are removed.
+        if series.cancellation().is_cancelled() {
+            break;
+        }
+        let permit = scheduler.volume().await?;
+        if series.cancellation().is_cancelled() {
+            break;
+        }
+
+        let budget = scheduler.budget();
+        let data = Arc::clone(&data);
+        let j: usize = data.chapters_per_volume[0..i].iter().sum();
+        let progress = PageProgress::new(
+            data.progress.clone(),
+            i + 1,
+            data.pages[j..(j + chapters)].iter().map(Vec::len).sum(),
+        );
+
+        handles.push(spawn_blocking(move || {
+            // Frees the slot of the volume once it is done
+            let _permit = permit;
+            let volume_name = volume_name(&data.name, i);
+
+            // EPUB books and PDF documents are kept in memory until they are saved, CBZ files are written right away
+            let _reservation = match file_format {
+                FileFormat::CBZ => None,
+                FileFormat::EPUB => Some(budget.reserve(data.pages[j..(j + chapters)].iter().flatten(), Footprint::FILE)?),
+                FileFormat::PDF => Some(budget.reserve(data.pages[j..(j + chapters)].iter().flatten(), Footprint::DECODED)?),
+            };
+
+            match file_format {
+                FileFormat::CBZ => {
+                    let mut cbz = cbz::Cbz::new(&data.target_directory, &volume_name)?;
+                    cbz.set_progress(progress)
+                        .set_cancellation(data.cancellation.clone())
+                        .set_budget(budget.clone());
+
+                    for k in j..(j + chapters) {
+                        cbz.add_pages(&data.pages[k])
+                            .map_err(|e| e.with_chapter(k + 1))?;
+                    }
+
+                    cbz.set_comicinfo(&volume_name, i + 1)?;
+                    cbz.save()
+                }
+                FileFormat::EPUB => {
+                    let mut epub = epub::EPub::new()?;
+
+                    epub.set_progress(progress)
+                        .set_cancellation(data.cancellation.clone())
+                        .set_budget(budget.clone())
+                        .set_cover(&data.pages[j][0])?
+                        .set_lang("en")?
+                        .set_metadata("title", &volume_name)?
+                        .set_metadata("author", "Manga Bundler")?
+                        .set_metadata(
+                            "direction",
+                            if direction == Direction::LTR {
+                                "ltr"
+                            } else {
+                                "rtl"
+                            },
+                        )?;
+
+                    for k in j..(j + chapters) {
+                        epub.add_chapter(k + 1, &data.pages[k])
+                            .map_err(|e| e.with_chapter(k + 1))?;
+                    }
+
+                    epub.save(&data.target_directory, &volume_name)
+                }
+                FileFormat::PDF => {
+                    let mut pdf = pdf::Pdf::new(&volume_name)?;
+                    pdf.set_progress(progress)
+                        .set_cancellation(data.cancellation.clone())
+                        .set_budget(budget.clone());
+
+                    for k in j..(j + chapters) {
+                        pdf.add_pages(&data.pages[k])
+                            .map_err(|e| e.with_chapter(k + 1))?;
+                    }
+
+                    pdf.save(&data.target_directory, &volume_name)
+                }
+            }
+        }));
+    }
+
+    // Wait for all threads to finish and report the result of every volume
+    let mut volumes: Vec<VolumeReport> = Vec::with_capacity(handles.len());
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        let result = match handle.await {
+            Ok(result) => result,
+            Err(e) => Err(Error::AsyncTaskError(e.to_string())),
+        };
+
+        volumes.push(match result {
+            Ok(path) => VolumeReport {
+                volume: i + 1,
+                name: volume_name(&series.name, i),
+                success: true,
+                size: path.metadata().ok().map(|metadata| metadata.len()),
+                path: Some(path),
+                error: None,
+            },
+            Err(e) => {
+                // Remove whatever was already written of the failed volume, an existing output is left untouched
+                let partial = partial_path(&Path::new(&data.target_directory).join(format!(
+                    "{}.{}",
+                    volume_name(&series.name, i),
+                    file_format.extension()
+                )));
+                if partial.exists() {
+                    let _ = remove_file(&partial).await;
+                }
+
+                VolumeReport {
+                    volume: i + 1,
+                    name: volume_name(&series.name, i),
+                    success: false,
+                    size: None,
+                    path: None,
+                    error: Some(ErrorReport::from(&e.with_volume(i + 1))),
+                }
+            }
+        });
+    }
+
+    // Volumes that finished before the cancellation are kept
+    if series.cancellation().is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+
+    let failed = volumes.iter().filter(|volume| !volume.success).count();
+
+    // Only fail the whole conversion if not a single volume could be converted
+    if failed > 0 && failed == volumes.len() {
+        let first = volumes[0].error.clone();
+        let error = Error::ConversionFailed(format!(
+            "All {} volumes failed, the first error was: {}",
+            failed,
+            first.as_ref().map(|e| e.message.as_str()).unwrap_or_default()
+        ));
+
+        return Err(match first.and_then(|e| e.path) {
+            Some(path) => error.with_path(&path).with_volume(1),
+            None => error.with_volume(1),
+        });
+    }
+
+    Ok(Conversion { failed, volumes })
+}
+
+pub fn volume_name(name: &str, index: usize) -> String {
+    format!("{} | {}", name, index + 1)
+}
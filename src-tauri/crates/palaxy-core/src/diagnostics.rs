@@ -0,0 +1,572 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::{Collector, NaturalSegment};
+use crate::naming::{NameParser, NameTemplate};
+use crate::prelude::*;
+use crate::rename::{check_inside, Rename, RenamePlan};
+
+lazy_static! {
+    static ref RE_DECIMAL: Regex = Regex::new(r"(\d+)\.(\d+)").unwrap();
+}
+
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub enum Severity {
+    INFO,
+    // Bundling works, but may be slower or less accurate
+    WARNING,
+    // The series can't be bundled until the problem is solved
+    ERROR,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiagnosticCode {
+    NoChapters,
+    EmptyChapters,
+    // Files that are neither chapters nor pages
+    StrayFiles,
+    ReadOnly,
+    NestedLayout,
+    NamingDetected,
+    // The chapters follow a known naming convention other than `VOLUME-CHAPTER`
+    NonDashedNaming,
+    UnknownNaming,
+    DirLacksNumber,
+    FileLacksNumber,
+    FallbackBundling,
+    MissingChapters,
+    DuplicateChapters,
+    // Decimals with a different number of places, like 12.9 and 12.10
+    AmbiguousDecimals,
+    // The volume number decreases while the chapter numbers increase
+    VolumeRegression,
+    MissingPages,
+    // Pages with the same page number, like 014.jpg and 14.png
+    DuplicatePages,
+    EmptyPages,
+}
+
+// A change to the source that solves a diagnostic, executed by `apply`
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub enum Fix {
+    RENAME(Vec<Rename>),
+    // Only files are deleted, never directories
+    DELETE(Vec<PathBuf>),
+}
+
+// A page a diagnostic refers to and its chapter.
+// Pages that do not exist (e.g. missing page numbers) have no path.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PageReference {
+    pub chapter: PathBuf,
+    pub page: Option<PathBuf>,
+    pub number: Option<f64>,
+}
+
+// A single finding of `analyze` and all paths it applies to
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub severity: Severity,
+    pub message: String,
+    pub paths: Vec<PathBuf>,
+    // Only given by checks of single pages
+    pub pages: Vec<PageReference>,
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    pub fn new(code: DiagnosticCode, severity: Severity, message: &str) -> Self {
+        Self {
+            code,
+            severity,
+            message: message.to_string(),
+            paths: Vec::new(),
+            pages: Vec::new(),
+            fix: None,
+        }
+    }
+
+    pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    pub fn with_pages(mut self, pages: Vec<PageReference>) -> Self {
+        self.pages = pages;
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Option<Fix>) -> Self {
+        self.fix = fix;
+        self
+    }
+}
+
+// Executes a fix and returns the number of changed paths.
+// Renames are journaled like the ones of the rename planner, so they can be undone as well.
+pub fn apply(fix: &Fix, source: &Path) -> Result<usize, Error> {
+    match fix {
+        Fix::RENAME(renames) => RenamePlan {
+            source: source.to_path_buf(),
+            renames: renames.clone(),
+        }
+        .apply(),
+        Fix::DELETE(paths) => {
+            for path in paths {
+                check_inside(path, source)?;
+
+                if !path.is_file() {
+                    return Err(Error::InvalidPath(path.clone(), "Only files can be deleted".to_string()));
+                }
+            }
+
+            for path in paths {
+                fs::remove_file(path).map_err(|e| Error::from(e).with_path(path))?;
+            }
+
+            Ok(paths.len())
+        }
+    }
+}
+
+// A chapter with its number and, if known, its volume
+struct NumberedChapter<'a> {
+    path: &'a PathBuf,
+    number: f64,
+    volume: Option<usize>,
+}
+
+// The decimal places of the chapter number as written in the name, e.g. "10" for "Chapter 12.10"
+fn decimal_places(chapter: &Path, number: f64) -> Option<String> {
+    RE_DECIMAL
+        .captures_iter(Collector::chapter_name(chapter)?)
+        .find(|captures| captures[0].parse::<f64>().ok() == Some(number))
+        .map(|captures| captures[2].to_string())
+}
+
+// Checks the chapter numbers for gaps, duplicates and ambiguous decimals. Chapters are numbered by the
+// naming convention or, like they are sorted by the collector, by the number following a chapter prefix.
+// If the numbers restart in every volume (by name or by volume directory), every volume is checked on its own,
+// otherwise chapters named with a volume are checked for volumes that decrease while the chapters increase.
+pub fn check_chapters(volumes: &[Vec<PathBuf>], template: Option<&NameTemplate>) -> Vec<Diagnostic> {
+    let parser = template.and_then(|template| NameParser::new(template).ok());
+    let nested = volumes.len() > 1;
+
+    let chapters: Vec<NumberedChapter> = volumes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, chapters)| chapters.iter().map(move |chapter| (i, chapter)))
+        .filter_map(|(i, path)| {
+            let name = parser.as_ref().and_then(|parser| parser.parse(path));
+
+            Some(NumberedChapter {
+                path,
+                number: name
+                    .as_ref()
+                    .and_then(|name| name.chapter)
+                    .or_else(|| Collector::chapter_number(path))?,
+                volume: match nested {
+                    true => Some(i + 1),
+                    false => name.and_then(|name| name.volume),
+                },
+            })
+        })
+        .collect();
+
+    // Numbers restart if every volume starts with the first chapter again
+    let mut starts: BTreeMap<usize, f64> = BTreeMap::new();
+    for chapter in &chapters {
+        if let Some(volume) = chapter.volume {
+            let start = starts.entry(volume).or_insert(chapter.number);
+            *start = start.min(chapter.number);
+        }
+    }
+    let restart = starts.len() > 1 && starts.values().all(|start| *start <= 1.0);
+
+    let mut groups: BTreeMap<Option<usize>, Vec<&NumberedChapter>> = BTreeMap::new();
+    for chapter in &chapters {
+        groups
+            .entry(if restart { chapter.volume } else { None })
+            .or_default()
+            .push(chapter);
+    }
+
+    let label = |volume: Option<usize>, number: f64| match (restart, volume) {
+        (true, Some(volume)) => format!("{} of volume {}", number, volume),
+        _ => number.to_string(),
+    };
+
+    let mut missing: Vec<String> = Vec::new();
+    let mut missing_paths: Vec<PathBuf> = Vec::new();
+    let mut duplicates: Vec<String> = Vec::new();
+    let mut duplicate_paths: Vec<PathBuf> = Vec::new();
+    let mut ambiguous_paths: Vec<PathBuf> = Vec::new();
+
+    for (volume, group) in groups.iter_mut() {
+        group.sort_by(|a, b| {
+            a.number
+                .partial_cmp(&b.number)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| Collector::sort_natural(a.path, b.path))
+        });
+
+        for pair in group.windows(2) {
+            let (previous, current) = (pair[0], pair[1]);
+
+            // Decimal chapters (e.g. 12.5) are extras, so only whole numbers are missed
+            let (from, to) = (previous.number.trunc() as u64, current.number.trunc() as u64);
+            if to > from + 1 {
+                missing.extend((from + 1..to).map(|number| label(*volume, number as f64)));
+                missing_paths.push(current.path.clone());
+            }
+
+            if previous.number == current.number {
+                if !duplicate_paths.contains(previous.path) {
+                    duplicates.push(label(*volume, current.number));
+                    duplicate_paths.push(previous.path.clone());
+                }
+                duplicate_paths.push(current.path.clone());
+            }
+        }
+
+        // Decimals of the same chapter are ambiguous if ordering them as numbers differs from ordering their places
+        let mut decimals: BTreeMap<u64, Vec<(&PathBuf, f64, u64)>> = BTreeMap::new();
+        for chapter in group.iter() {
+            if let Some(places) = decimal_places(chapter.path, chapter.number).and_then(|places| places.parse().ok()) {
+                decimals
+                    .entry(chapter.number.trunc() as u64)
+                    .or_default()
+                    .push((chapter.path, chapter.number, places));
+            }
+        }
+        for chapters in decimals.values() {
+            if chapters.windows(2).any(|pair| pair[0].1 != pair[1].1 && pair[0].2 > pair[1].2) {
+                ambiguous_paths.extend(chapters.iter().map(|(path, ..)| (*path).clone()));
+            }
+        }
+    }
+
+    // Chapters in the order of their numbers, whose volume is lower than the one of the chapter before
+    let mut regressions: Vec<PathBuf> = Vec::new();
+    if !restart && !nested {
+        if let Some(group) = groups.get(&None) {
+            for pair in group.windows(2) {
+                if let (Some(previous), Some(current)) = (pair[0].volume, pair[1].volume) {
+                    if current < previous {
+                        regressions.push(pair[1].path.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut report = |diagnostic: Diagnostic, paths: Vec<PathBuf>| {
+        if !paths.is_empty() {
+            diagnostics.push(diagnostic.with_paths(paths));
+        }
+    };
+
+    report(
+        Diagnostic::new(
+            DiagnosticCode::MissingChapters,
+            Severity::WARNING,
+            &format!("Chapters seem to be missing: {}.", missing.join(", ")),
+        ),
+        missing_paths,
+    );
+    report(
+        Diagnostic::new(
+            DiagnosticCode::DuplicateChapters,
+            Severity::WARNING,
+            &format!(
+                "Chapters exist more than once, e.g. from different scanlation groups: {}.",
+                duplicates.join(", ")
+            ),
+        ),
+        duplicate_paths,
+    );
+    report(
+        Diagnostic::new(
+            DiagnosticCode::AmbiguousDecimals,
+            Severity::WARNING,
+            "Decimal chapters with a different number of places (e.g. 12.9 and 12.10) may be sorted wrong. Use the same number of places.",
+        ),
+        ambiguous_paths,
+    );
+    report(
+        Diagnostic::new(
+            DiagnosticCode::VolumeRegression,
+            Severity::WARNING,
+            "The volume number decreases while the chapter numbers increase. Check the volume of these chapters.",
+        ),
+        regressions,
+    );
+
+    diagnostics
+}
+
+// The page numbers of a page name, e.g. [14] for "014.jpg" and [14, 15] for the double page "014-015.jpg".
+// The page number is the last number of the name, as pages may be prefixed with their chapter (e.g. "c012_p014.jpg").
+// Only a name of two consecutive numbers joined by "-" or "_" is a double page, "Ch12-013.jpg" is page 13.
+fn page_numbers(page: &PathBuf) -> Vec<f64> {
+    let stem = page.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let range = stem
+        .split_once(['-', '_'])
+        .filter(|(first, second)| [first, second].iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())))
+        .and_then(|(first, second)| Some((first.parse::<f64>().ok()?, second.parse::<f64>().ok()?)));
+
+    match range {
+        Some((first, second)) if second == first + 1.0 => vec![first, second],
+        _ => Collector::natural_key(page)
+            .iter()
+            .rev()
+            .find_map(|segment| match segment {
+                NaturalSegment::Number(number) => Some(*number),
+                NaturalSegment::Text(_) => None,
+            })
+            .into_iter()
+            .collect(),
+    }
+}
+
+// Checks the pages of every chapter for missing page numbers, page numbers used by several pages
+// (e.g. 014.jpg and 14.png) and empty files. Missing pages are only checked if every page of the chapter has a number.
+pub fn check_pages(chapters: &[PathBuf], pages: &[Vec<PathBuf>]) -> Vec<Diagnostic> {
+    let results: Vec<(Vec<PageReference>, Vec<PageReference>, Vec<PageReference>)> = chapters
+        .par_iter()
+        .zip(pages)
+        .map(|(chapter, pages)| {
+            let reference = |page: Option<&PathBuf>, number: Option<f64>| PageReference {
+                chapter: chapter.clone(),
+                page: page.cloned(),
+                number,
+            };
+            let pages: Vec<&PathBuf> = pages.iter().filter(|page| get_file_info(page).is_ok()).collect();
+
+            let numbers: Vec<Vec<f64>> = pages.iter().map(|page| page_numbers(page)).collect();
+            let mut missing = Vec::new();
+            if numbers.iter().all(|numbers| !numbers.is_empty()) {
+                // Decimal pages (e.g. 14.5) are inserts, so only whole numbers are missed.
+                // Pages may be numbered across the volume, so only gaps between the lowest and highest page are missed.
+                let mut whole: Vec<u64> = numbers.iter().flatten().map(|number| number.trunc() as u64).collect();
+                whole.sort_unstable();
+                whole.dedup();
+
+                let first = whole.first().copied().unwrap_or_default();
+                let last = whole.last().copied().unwrap_or_default();
+                missing.extend(
+                    (first..last)
+                        .filter(|number| whole.binary_search(number).is_err())
+                        .map(|number| reference(None, Some(number as f64))),
+                );
+            }
+
+            // Pages without a number can only be duplicates by their name
+            let mut groups: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+            for (page, numbers) in pages.iter().zip(&numbers) {
+                let keys: Vec<String> = match numbers.is_empty() {
+                    true => page.file_stem().map(|stem| stem.to_string_lossy().to_string()).into_iter().collect(),
+                    false => numbers.iter().map(f64::to_string).collect(),
+                };
+                for key in keys {
+                    groups.entry(key).or_default().push(page);
+                }
+            }
+            let mut duplicate_pages: Vec<&PathBuf> = groups
+                .into_values()
+                .filter(|pages| pages.len() > 1)
+                .flatten()
+                .collect();
+            duplicate_pages.sort();
+            duplicate_pages.dedup();
+            let duplicates: Vec<PageReference> = duplicate_pages
+                .into_iter()
+                .map(|page| reference(Some(page), page_numbers(page).first().copied()))
+                .collect();
+
+            let sizes = archive::sizes(&pages.iter().map(|page| page.to_path_buf()).collect::<Vec<PathBuf>>()).unwrap_or_default();
+            let empty = pages
+                .iter()
+                .zip(sizes)
+                .filter(|(_, size)| *size == 0)
+                .map(|(page, _)| reference(Some(page), page_numbers(page).first().copied()))
+                .collect();
+
+            (missing, duplicates, empty)
+        })
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    let mut report = |code: DiagnosticCode, severity: Severity, message: &str, pages: Vec<PageReference>, fix: bool| {
+        if pages.is_empty() {
+            return;
+        }
+
+        // Missing pages refer to their chapter, all others to the pages themselves
+        let mut paths: Vec<PathBuf> = pages
+            .iter()
+            .map(|page| page.page.clone().unwrap_or_else(|| page.chapter.clone()))
+            .collect();
+        paths.dedup();
+
+        diagnostics.push(
+            Diagnostic::new(code, severity, message)
+                // Pages inside of archives can't be deleted on their own
+                .with_fix(match fix && paths.iter().all(|path| path.is_file()) {
+                    true => Some(Fix::DELETE(paths.clone())),
+                    false => None,
+                })
+                .with_paths(paths)
+                .with_pages(pages),
+        );
+    };
+
+    let (mut missing, mut duplicates, mut empty) = (Vec::new(), Vec::new(), Vec::new());
+    for (chapter_missing, chapter_duplicates, chapter_empty) in results {
+        missing.extend(chapter_missing);
+        duplicates.extend(chapter_duplicates);
+        empty.extend(chapter_empty);
+    }
+
+    let description = missing
+        .iter()
+        .fold(BTreeMap::new(), |mut chapters: BTreeMap<&PathBuf, Vec<String>>, page| {
+            if let Some(number) = page.number {
+                chapters.entry(&page.chapter).or_default().push(number.to_string());
+            }
+            chapters
+        })
+        .into_iter()
+        .map(|(chapter, numbers)| {
+            let name = Collector::chapter_name(chapter).unwrap_or_default();
+            format!("{} of '{}'", numbers.join(", "), name)
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    report(
+        DiagnosticCode::MissingPages,
+        Severity::WARNING,
+        &format!("Pages seem to be missing: {}.", description),
+        missing,
+        false,
+    );
+    report(
+        DiagnosticCode::DuplicatePages,
+        Severity::WARNING,
+        "Some pages share the same page number (e.g. 014.jpg and 14.png). Only one of them should be kept.",
+        duplicates,
+        false,
+    );
+    report(
+        DiagnosticCode::EmptyPages,
+        Severity::ERROR,
+        "Some pages are empty files, which can't be converted. Remove them to continue.",
+        empty,
+        true,
+    );
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|name| PathBuf::from("/series").join(name)).collect()
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<DiagnosticCode> {
+        diagnostics.iter().map(|diagnostic| diagnostic.code).collect()
+    }
+
+    #[test]
+    fn check_chapters_finds_gaps_and_duplicates() {
+        let diagnostics = check_chapters(&[paths(&["Ch.1", "Ch.2", "Ch.2 [Other]", "Ch.2.5", "Ch.5"])], None);
+
+        assert_eq!(codes(&diagnostics), vec![DiagnosticCode::MissingChapters, DiagnosticCode::DuplicateChapters]);
+        assert!(diagnostics[0].message.contains("3, 4"));
+        assert_eq!(diagnostics[1].paths, paths(&["Ch.2", "Ch.2 [Other]"]));
+    }
+
+    #[test]
+    fn check_chapters_checks_restarting_volumes_on_their_own() {
+        let nested = [paths(&["Volume 1/Ch.1", "Volume 1/Ch.2"]), paths(&["Volume 2/Ch.1", "Volume 2/Ch.2"])];
+        assert!(check_chapters(&nested, None).is_empty());
+
+        let named = paths(&["001-001", "001-002", "002-001", "002-003"]);
+        let diagnostics = check_chapters(&[named], Some(&NameTemplate::DASHED));
+        assert_eq!(codes(&diagnostics), vec![DiagnosticCode::MissingChapters]);
+        assert!(diagnostics[0].message.contains("2 of volume 2"));
+
+        let regression = paths(&["001-001", "002-002", "001-003"]);
+        let diagnostics = check_chapters(&[regression], Some(&NameTemplate::DASHED));
+        assert_eq!(codes(&diagnostics), vec![DiagnosticCode::VolumeRegression]);
+        assert_eq!(diagnostics[0].paths, paths(&["001-003"]));
+    }
+
+    #[test]
+    fn page_numbers_only_treat_plain_ranges_as_double_pages() {
+        let numbers = |name: &str| page_numbers(&PathBuf::from(name));
+
+        assert_eq!(numbers("014.jpg"), vec![14.0]);
+        assert_eq!(numbers("014-015.jpg"), vec![14.0, 15.0]);
+        assert_eq!(numbers("014_015.jpg"), vec![14.0, 15.0]);
+        assert_eq!(numbers("Ch12-013.png"), vec![13.0]);
+        assert_eq!(numbers("c012_p014.jpg"), vec![14.0]);
+        assert_eq!(numbers("012-014.jpg"), vec![14.0]);
+        assert!(numbers("cover.jpg").is_empty());
+    }
+
+    #[test]
+    fn check_pages_finds_missing_duplicate_and_empty_pages() {
+        let directory = std::env::temp_dir().join(format!("palaxy-check-pages-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let chapters: Vec<PathBuf> = ["001-001", "001-002", "001-003"].iter().map(|name| directory.join(name)).collect();
+        let files: [&[&str]; 3] = [
+            // Numbered across the volume, only page 9 is missing
+            &["006.png", "007.png", "008-009.png", "011.png"],
+            &["Ch12-013.png", "Ch12-014.png", "014.jpg"],
+            &["1.png", "2.png"],
+        ];
+
+        let mut pages = Vec::new();
+        for (chapter, files) in chapters.iter().zip(files) {
+            fs::create_dir_all(chapter).unwrap();
+            pages.push(
+                files
+                    .iter()
+                    .map(|file| {
+                        let page = chapter.join(file);
+                        fs::write(&page, if *file == "2.png" { "" } else { "page" }).unwrap();
+                        page
+                    })
+                    .collect::<Vec<PathBuf>>(),
+            );
+        }
+
+        let diagnostics = check_pages(&chapters, &pages);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            codes(&diagnostics),
+            vec![DiagnosticCode::MissingPages, DiagnosticCode::DuplicatePages, DiagnosticCode::EmptyPages]
+        );
+        let numbers = |diagnostic: &Diagnostic| -> Vec<Option<f64>> { diagnostic.pages.iter().map(|page| page.number).collect() };
+        assert_eq!(numbers(&diagnostics[0]), vec![Some(10.0)]);
+        assert_eq!(diagnostics[1].paths, vec![pages[1][2].clone(), pages[1][1].clone()]);
+        assert_eq!(diagnostics[2].paths, vec![pages[2][1].clone()]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::Collector;
+
+lazy_static! {
+    // e.g. "Ch.112 [GroupA]" or "Ch.112 (GroupA)"
+    static ref RE_GROUP: Regex = Regex::new(r"\[([^\]]+)\]|\(([^)]+)\)").unwrap();
+}
+
+// Decides which of several chapters with the same number (e.g. from different scanlation groups) is bundled
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub enum DuplicatePolicy {
+    // Scanlation groups in the order of preference
+    GROUPS(Vec<String>),
+    PAGES,
+    // The largest middle page, as the first and last pages are often credits
+    RESOLUTION,
+    // The chosen chapters, duplicates without a choice are all kept
+    MANUAL(Vec<PathBuf>),
+}
+
+// Chapters with the same number and the one that was chosen by the policy.
+// Without a choice (e.g. no preferred group was found), all candidates are kept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DuplicateChoice {
+    pub volume: Option<usize>,
+    pub chapter: f64,
+    pub candidates: Vec<PathBuf>,
+    pub chosen: Option<PathBuf>,
+    pub rejected: Vec<PathBuf>,
+}
+
+// The scanlation group of a chapter, given in brackets or as prefix of Mihon downloads ("Group_Vol.2 Ch.32")
+pub fn group_name(chapter: &Path) -> Option<String> {
+    let name = Collector::chapter_name(chapter)?;
+
+    RE_GROUP
+        .captures(name)
+        .and_then(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .map(|group| group.as_str().trim().to_string())
+        .or_else(|| {
+            name.split_once('_')
+                .map(|(group, _)| group.trim().to_string())
+                .filter(|group| group.chars().any(char::is_alphabetic))
+        })
+}
+
+// The pixel count of the middle page, or 0 if it can't be read
+fn resolution(pages: &[PathBuf]) -> u64 {
+    pages
+        .get(pages.len() / 2)
+        .and_then(|page| archive::read(page).ok())
+        .and_then(|data| {
+            image::ImageReader::new(Cursor::new(data))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()
+        })
+        .map_or(0, |(width, height)| width as u64 * height as u64)
+}
+
+// Groups the chapters by their volume and number and chooses one chapter of every group with more than one.
+// The chapters are given with their pages and their number, chapters without a number are never duplicates.
+pub fn resolve(
+    chapters: &[PathBuf],
+    pages: &[Vec<PathBuf>],
+    numbers: &[Option<(Option<usize>, f64)>],
+    policy: &DuplicatePolicy,
+) -> Vec<DuplicateChoice> {
+    let mut groups: Vec<(Option<usize>, f64, Vec<usize>)> = Vec::new();
+    let mut known: HashMap<(Option<usize>, u64), usize> = HashMap::new();
+
+    for (i, number) in numbers.iter().enumerate() {
+        if let Some((volume, chapter)) = number {
+            let group = *known.entry((*volume, chapter.to_bits())).or_insert_with(|| {
+                groups.push((*volume, *chapter, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].2.push(i);
+        }
+    }
+
+    groups
+        .into_par_iter()
+        .filter(|(_, _, candidates)| candidates.len() > 1)
+        .map(|(volume, chapter, candidates)| {
+            // The first candidate with the highest score is chosen
+            let choose = |score: &dyn Fn(usize) -> Option<u64>| {
+                candidates
+                    .iter()
+                    .filter_map(|&i| score(i).map(|score| (i, score)))
+                    .fold(None, |best: Option<(usize, u64)>, (i, score)| match best {
+                        Some((_, best_score)) if best_score >= score => best,
+                        _ => Some((i, score)),
+                    })
+                    .map(|(i, _)| i)
+            };
+
+            let chosen = match policy {
+                DuplicatePolicy::GROUPS(preferred) => choose(&|i| {
+                    let group = group_name(&chapters[i])?;
+                    preferred
+                        .iter()
+                        .position(|preferred| preferred.trim().eq_ignore_ascii_case(&group))
+                        .map(|position| (preferred.len() - position) as u64)
+                }),
+                DuplicatePolicy::PAGES => choose(&|i| Some(pages[i].len() as u64)),
+                DuplicatePolicy::RESOLUTION => choose(&|i| Some(resolution(&pages[i]))),
+                DuplicatePolicy::MANUAL(choices) => candidates
+                    .iter()
+                    .copied()
+                    .find(|&i| choices.contains(&chapters[i])),
+            };
+
+            DuplicateChoice {
+                volume,
+                chapter,
+                candidates: candidates.iter().map(|&i| chapters[i].clone()).collect(),
+                chosen: chosen.map(|i| chapters[i].clone()),
+                rejected: match chosen {
+                    Some(chosen) => candidates
+                        .iter()
+                        .filter(|&&i| i != chosen)
+                        .map(|&i| chapters[i].clone())
+                        .collect(),
+                    None => Vec::new(),
+                },
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use zip::write::{SimpleFileOptions};
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::archive;
+use crate::generator::{finish, partial_path, PageProgress};
+use crate::prelude::*;
+use crate::scheduler::{Footprint, PageBudget};
+
+pub struct Cbz {
+    path: PathBuf,
+    zip: ZipWriter<File>,
+    options: SimpleFileOptions,
+    page_index: usize,
+    progress: PageProgress,
+    cancellation: CancellationToken,
+    budget: Arc<PageBudget>,
+}
+
+impl Cbz {
+    pub fn new(output_path: &str, filename: &str) -> Result<Self, Error> {
+        let options: SimpleFileOptions = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(0o755);
+
+        let path = PathBuf::from(format!("{}/{}.cbz", output_path, filename));
+        let zip = ZipWriter::new(File::create(partial_path(&path))?);
+
+        Ok(Cbz {
+            path,
+            zip,
+            options,
+            page_index: 0,
+            progress: PageProgress::default(),
+            cancellation: CancellationToken::default(),
+            budget: Arc::new(PageBudget::default()),
+        })
+    }
+
+    pub fn set_progress(&mut self, progress: PageProgress) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn set_cancellation(&mut self, cancellation: CancellationToken) -> &mut Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn set_budget(&mut self, budget: Arc<PageBudget>) -> &mut Self {
+        self.budget = budget;
+        self
+    }
+
+    // Pages are read in parallel, but written one after another to keep their order
+    pub fn add_pages(&mut self, image_paths: &[PathBuf]) -> Result<&mut Self, Error> {
+        let budget = self.budget.clone();
+        let cancellation = self.cancellation.clone();
+        let reader = archive::Reader::default();
+
+        budget.load(image_paths, Footprint::FILE, &cancellation, |path| reader.read(path), |image_path, image_file| {
+            self.write_page(image_path, image_file)
+        })?;
+
+        Ok(self)
+    }
+
+    fn write_page(&mut self, image_path: &PathBuf, image_file: Vec<u8>) -> Result<(), Error> {
+        let (image_extension, _) = get_file_info(image_path)?;
+
+        self.page_index += 1;
+
+        self.zip.start_file(
+            format!("page_{:03}.{}", self.page_index, image_extension),
+            self.options,
+        )?;
+        self.zip.write_all(&image_file)?;
+        self.progress.advance(image_path);
+
+        Ok(())
+    }
+
+    pub fn set_comicinfo(&mut self, title: &str, volume: usize) -> Result<&mut Self, Error> {
+        const TEMPLATE: &str = include_str!("../../templates/template.xml");
+
+        let xml = TEMPLATE
+            .replace("%title%", title)
+            .replace("%volume%", &*volume.to_string())
+            .replace("%pagecount%", &*self.page_index.to_string());
+
+        self.zip.start_file("ComicInfo.xml", self.options)?;
+        self.zip.write_all(xml.as_bytes())?;
+
+        Ok(self)
+    }
+
+    pub fn save(self) -> Result<PathBuf, Error> {
+        self.zip.finish()?;
+        finish(&self.path)
+    }
+}
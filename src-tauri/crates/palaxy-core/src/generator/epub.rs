@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use epub_builder::{EpubBuilder, EpubContent, EpubVersion, ZipLibrary};
+
+use crate::archive;
+use crate::generator::{finish, partial_path, PageProgress};
+use crate::prelude::*;
+use crate::scheduler::{Footprint, PageBudget};
+
+fn generate_xhtml(image_source: &str) -> Result<String, Error> {
+    const TEMPLATE: &str = include_str!("../../templates/template.xhtml");
+    let xhtml = TEMPLATE
+        .replace("%title%", image_source)
+        .replace("%src%", image_source)
+        .replace("%alt%", image_source);
+    Ok(xhtml)
+}
+
+pub struct EPub {
+    epub: EpubBuilder<ZipLibrary>,
+    progress: PageProgress,
+    cancellation: CancellationToken,
+    budget: Arc<PageBudget>,
+}
+
+impl EPub {
+    pub fn new() -> Result<Self, Error> {
+        let mut epub = EpubBuilder::new(ZipLibrary::new()?)?;
+
+        epub.epub_version(EpubVersion::V30);
+        epub.stylesheet(include_bytes!("../../templates/template.css").as_slice())?;
+
+        Ok(EPub {
+            epub,
+            progress: PageProgress::default(),
+            cancellation: CancellationToken::default(),
+            budget: Arc::new(PageBudget::default()),
+        })
+    }
+
+    pub fn set_progress(&mut self, progress: PageProgress) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn set_cancellation(&mut self, cancellation: CancellationToken) -> &mut Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn set_budget(&mut self, budget: Arc<PageBudget>) -> &mut Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, Error> {
+        self.epub.metadata(key, value)?;
+        Ok(self)
+    }
+
+    pub fn set_cover(&mut self, cover_image_path: &PathBuf) -> Result<&mut Self, Error> {
+        let (cover_extension, cover_mime) = get_file_info(cover_image_path)?;
+        let cover_file = Cursor::new(archive::read(cover_image_path)?);
+
+        self.epub.add_cover_image(
+            format!("data/cover.{}", cover_extension),
+            cover_file,
+            cover_mime,
+        )?;
+        Ok(self)
+    }
+
+    pub fn set_lang(&mut self, lang: &str) -> Result<&mut Self, Error> {
+        self.epub.set_lang(lang);
+        Ok(self)
+    }
+
+    // Pages are read in parallel, but added one after another to keep their order
+    pub fn add_chapter(
+        &mut self,
+        chapter_count: usize,
+        image_paths: &[PathBuf],
+    ) -> Result<&mut Self, Error> {
+        let budget = self.budget.clone();
+        let cancellation = self.cancellation.clone();
+        let reader = archive::Reader::default();
+        let mut i = 0;
+
+        budget.load(image_paths, Footprint::FILE, &cancellation, |path| reader.read(path), |path, image_file| {
+            i += 1;
+            self.write_page(chapter_count, i, path, image_file)
+        })?;
+
+        Ok(self)
+    }
+
+    fn write_page(
+        &mut self,
+        chapter_count: usize,
+        page_count: usize,
+        path: &PathBuf,
+        image_file: Vec<u8>,
+    ) -> Result<(), Error> {
+        let (image_extension, image_mime) = get_file_info(path)?;
+
+        let image_name = format!("images/{}/{}.{}", chapter_count, page_count, image_extension);
+        let image_xhtml = generate_xhtml(&image_name)?;
+
+        self.epub
+            .add_resource(&image_name, Cursor::new(image_file), image_mime)?;
+
+        self.epub.add_content(EpubContent::new(
+            format!("{}-{}.xhtml", chapter_count, page_count),
+            image_xhtml.as_bytes(),
+        ))?;
+
+        self.progress.advance(path);
+        Ok(())
+    }
+
+    pub fn save(&mut self, output_path: &str, file_name: &str) -> Result<PathBuf, Error> {
+        let output_path = Path::new(output_path);
+        let output_file_path = output_path.join(format!("{}.epub", file_name));
+
+        let file = File::create(partial_path(&output_file_path))?;
+
+        self.epub.generate(file)?;
+        finish(&output_file_path)
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+pub mod cbz;
+pub mod epub;
+pub mod pdf;
+
+// Volumes are written to a temporary file next to their output, which only replaces the output once it is complete.
+// A failed or cancelled volume never touches an output of an earlier conversion that way.
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_os_string();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+// Moves the complete temporary file of a volume to its output
+fn finish(path: &Path) -> Result<PathBuf, Error> {
+    std::fs::rename(partial_path(path), path).map_err(|e| Error::from(e).with_path(path))?;
+    Ok(path.to_path_buf())
+}
+
+// Reports every page that is added to a volume
+#[derive(Clone, Default)]
+pub struct PageProgress {
+    reporter: Option<ProgressReporter>,
+    volume: usize,
+    index: usize,
+    total: usize,
+}
+
+impl PageProgress {
+    pub fn new(reporter: Option<ProgressReporter>, volume: usize, total: usize) -> Self {
+        Self {
+            reporter,
+            volume,
+            index: 0,
+            total,
+        }
+    }
+
+    pub fn advance(&mut self, page: &Path) {
+        self.index += 1;
+
+        if let Some(reporter) = &self.reporter {
+            reporter(Progress {
+                stage: Stage::CONVERT,
+                series: None,
+                volume: Some(self.volume),
+                index: self.index,
+                total: self.total,
+                file: Some(page.to_path_buf()),
+            });
+        }
+    }
+}
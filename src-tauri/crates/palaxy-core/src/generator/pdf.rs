@@ -0,0 +1,140 @@
+extern crate printpdf;
+
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use image_crate::codecs::*;
+use printpdf::*;
+
+use crate::archive;
+use crate::generator::{finish, partial_path, PageProgress};
+use crate::prelude::*;
+use crate::scheduler::{Footprint, PageBudget};
+// Needed to override the Error impl from printpdf
+use crate::prelude::Error;
+
+const DPI: f32 = 300.0;
+
+pub struct Pdf {
+    title: String,
+    // The document is created with the first page, as it needs the dimensions of that page
+    document: Option<PdfDocumentReference>,
+    progress: PageProgress,
+    cancellation: CancellationToken,
+    budget: Arc<PageBudget>,
+}
+
+fn get_image(reader: &archive::Reader, image_path: &PathBuf) -> Result<Image, Error> {
+    let (image_extension, _) = get_file_info(image_path)?;
+    let mut image_file = Cursor::new(reader.read(image_path)?);
+
+    let image = match image_extension {
+        "png" => Image::try_from(png::PngDecoder::new(&mut image_file)?)?,
+        "jpg" | "jpeg" => Image::try_from(jpeg::JpegDecoder::new(&mut image_file)?)?,
+        "bmp" => Image::try_from(bmp::BmpDecoder::new(&mut image_file)?)?,
+        // printpdf can't decode WebP, so the page is decoded here and embedded as raw pixels
+        "webp" => {
+            let image = ::image::load_from_memory_with_format(image_file.get_ref(), ::image::ImageFormat::WebP)?.to_rgb8();
+
+            Image::from(ImageXObject {
+                width: Px(image.width() as usize),
+                height: Px(image.height() as usize),
+                color_space: ColorSpace::Rgb,
+                bits_per_component: ColorBits::Bit8,
+                interpolate: true,
+                image_data: image.into_raw(),
+                image_filter: None,
+                smask: None,
+                clipping_bbox: None,
+            })
+        }
+        _ => return Err(Error::Unsupported("Image format not supported".to_string())),
+    };
+
+    Ok(image)
+}
+
+fn pixel_to_mm(pixels: usize) -> f32 {
+    // https://pixelcalculator.com/de
+    // *10 to convert from cm to mm
+    (pixels as f32 / DPI) * 2.54 * 10f32
+}
+
+impl Pdf {
+    pub fn new(title: &str) -> Result<Self, Error> {
+        Ok(Pdf {
+            title: title.to_string(),
+            document: None,
+            progress: PageProgress::default(),
+            cancellation: CancellationToken::default(),
+            budget: Arc::new(PageBudget::default()),
+        })
+    }
+
+    pub fn set_progress(&mut self, progress: PageProgress) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn set_cancellation(&mut self, cancellation: CancellationToken) -> &mut Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn set_budget(&mut self, budget: Arc<PageBudget>) -> &mut Self {
+        self.budget = budget;
+        self
+    }
+
+    // Pages are decoded in parallel, but added one after another to keep their order
+    pub fn add_pages(&mut self, image_paths: &[PathBuf]) -> Result<&mut Self, Error> {
+        let budget = self.budget.clone();
+        let cancellation = self.cancellation.clone();
+        let reader = archive::Reader::default();
+        let load = |image_path: &PathBuf| get_image(&reader, image_path);
+
+        budget.load(image_paths, Footprint::DECODED, &cancellation, load, |image_path, image| {
+            self.write_page(image_path, image)
+        })?;
+
+        Ok(self)
+    }
+
+    fn write_page(&mut self, image_path: &Path, image: Image) -> Result<(), Error> {
+        let width = Mm(pixel_to_mm(image.image.width.0));
+        let height = Mm(pixel_to_mm(image.image.height.0));
+
+        let edit_layer = match &self.document {
+            Some(document) => {
+                let (page, layer) = document.add_page(width, height, "Layer 1");
+                document.get_page(page).get_layer(layer)
+            }
+            None => {
+                let (document, page, layer) = PdfDocument::new(&self.title, width, height, "Layer 1");
+                let edit_layer = document.get_page(page).get_layer(layer);
+                self.document = Some(document);
+                edit_layer
+            }
+        };
+
+        image.add_to_layer(edit_layer, ImageTransform::default());
+        self.progress.advance(image_path);
+
+        Ok(())
+    }
+
+    pub fn save(self, output_path: &str, filename: &str) -> Result<PathBuf, Error> {
+        let document = self
+            .document
+            .ok_or_else(|| Error::NotFound(format!("Pages for '{}'", self.title)))?;
+
+        let path = PathBuf::from(format!("{}/{}.pdf", output_path, filename));
+        let mut buffer = BufWriter::new(File::create(partial_path(&path))?);
+
+        document.save(&mut buffer)?;
+        buffer.into_inner().map_err(|e| Error::from(e.into_error()))?;
+        finish(&path)
+    }
+}
@@ -0,0 +1,39 @@
+// The conversion logic of Palaxy, independent of any user interface.
+// A series is analyzed, planned into volumes and then converted:
+//
+//     let series = Series::new(&source);
+//     let plan = plan(&series, &PlanOptions::default()).await?;
+//     let conversion = convert(&series, &plan, &ConvertOptions::default()).await?;
+//
+// A library converts every series inside of a root directory as a queue:
+//
+//     let mut library = Library::new(&root);
+//     library.scan().await?.prepare(&PlanOptions::default()).await?;
+//     let report = library.convert(&ConvertOptions::default()).await?;
+
+pub mod archive;
+pub mod collector;
+pub mod convert;
+pub mod diagnostics;
+pub mod duplicates;
+pub mod generator;
+pub mod library;
+pub mod naming;
+pub mod plan;
+pub mod plan_file;
+pub mod prelude;
+pub mod project;
+pub mod rename;
+pub mod scheduler;
+pub mod series;
+
+pub use convert::{convert, Conversion, ConvertOptions};
+pub use diagnostics::{Diagnostic, Fix, PageReference};
+pub use duplicates::{DuplicateChoice, DuplicatePolicy};
+pub use library::{Library, LibraryEntry, LibraryReport};
+pub use plan::{plan, Plan, PlanIssue, PlanOptions};
+pub use plan_file::PlanFile;
+pub use prelude::Error;
+pub use project::Project;
+pub use rename::{RenameOptions, RenamePlan};
+pub use series::{analyze, Analysis, Series};
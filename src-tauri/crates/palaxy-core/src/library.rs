@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::read_dir;
+
+use crate::archive;
+use crate::collector::Collector;
+use crate::convert::{convert, Conversion, ConvertOptions};
+use crate::naming::NameTemplate;
+use crate::plan::{plan, Plan, PlanOptions};
+use crate::prelude::*;
+use crate::series::{analyze, Analysis, Series};
+
+// A series found inside of a library root, with its own plan
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LibraryEntry {
+    pub source: PathBuf,
+    pub name: String,
+    pub name_template: NameTemplate,
+    // Replaces the options of the library for this series, including the recommended bundle flag
+    pub options: Option<PlanOptions>,
+    // Skipped series are neither bundled nor converted
+    pub skip: bool,
+    pub analysis: Option<Analysis>,
+    pub plan: Option<Plan>,
+    // Why the series could not be analyzed or bundled
+    pub error: Option<ErrorReport>,
+}
+
+impl LibraryEntry {
+    pub fn new(source: &Path) -> Self {
+        let series = Series::new(source);
+
+        Self {
+            source: series.source,
+            name: series.name,
+            name_template: series.name_template,
+            options: None,
+            skip: false,
+            analysis: None,
+            plan: None,
+            error: None,
+        }
+    }
+}
+
+// The result of a single series of a batch conversion
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SeriesReport {
+    pub name: String,
+    pub source: PathBuf,
+    pub skipped: bool,
+    pub conversion: Option<Conversion>,
+    pub error: Option<ErrorReport>,
+}
+
+// The aggregate result of a batch conversion. A series counts as failed if any of its volumes failed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LibraryReport {
+    pub converted: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub volumes: usize,
+    pub series: Vec<SeriesReport>,
+}
+
+// A directory of series, which are analyzed, bundled and converted one after another
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Library {
+    pub root: PathBuf,
+    pub entries: Vec<LibraryEntry>,
+    #[serde(skip)]
+    progress: Option<ProgressReporter>,
+    #[serde(skip)]
+    cancellation: CancellationToken,
+}
+
+impl Library {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    // Every directory of the root that contains chapters (or volume directories) is a series.
+    // Loose files and directories with pages directly inside are ignored.
+    // Series with stray files next to their chapters are kept, so that `prepare` reports them as failed.
+    pub async fn scan(&mut self) -> Result<&mut Self, Error> {
+        let mut directories: Vec<PathBuf> = Vec::new();
+        let mut paths = read_dir(&self.root).await?;
+
+        while let Some(path) = paths.next_entry().await? {
+            if path.path().is_dir() && !path.file_name().to_string_lossy().starts_with('.') {
+                directories.push(path.path());
+            }
+        }
+
+        directories.sort_by(Collector::sort_natural);
+        self.entries.clear();
+
+        for directory in directories {
+            self.cancellation.check()?;
+
+            let is_series = match Collector::collect(&directory, true).await {
+                Ok(chapters) => !chapters.is_empty(),
+                Err(_) => has_chapters(&directory).await,
+            };
+
+            if is_series {
+                self.entries.push(LibraryEntry::new(&directory));
+            }
+        }
+
+        Ok(self)
+    }
+
+    // The series of an entry, reporting its position in the library with every progress
+    fn series(&self, index: usize, entry: &LibraryEntry) -> Series {
+        let series = Series::new(&entry.source)
+            .with_name(&entry.name)
+            .with_name_template(entry.name_template.clone())
+            .with_cancellation(self.cancellation.clone());
+
+        match &self.progress {
+            Some(progress) => {
+                let progress = progress.clone();
+                series.with_progress(Arc::new(move |mut update: Progress| {
+                    update.series = Some(index + 1);
+                    progress(update)
+                }))
+            }
+            None => series,
+        }
+    }
+
+    // Analyzes and bundles every series that is not skipped.
+    // Series without own options use the given ones with the bundle flag recommended by `analyze`.
+    // A failing series does not stop the others, its error is kept in the entry instead.
+    pub async fn prepare(&mut self, options: &PlanOptions) -> Result<&mut Self, Error> {
+        for i in 0..self.entries.len() {
+            let mut entry = self.entries[i].clone();
+
+            if entry.skip {
+                continue;
+            }
+
+            entry.plan = None;
+            entry.error = None;
+
+            let result = async {
+                let analysis = analyze(&self.series(i, &entry)).await?;
+
+                // The detected naming convention is used for bundling by name, just like for a single series
+                if let (BundleFlag::NAME, Some(template)) = (&analysis.flag, &analysis.template) {
+                    entry.name_template = template.clone();
+                }
+
+                let options = match &entry.options {
+                    Some(options) => options.clone(),
+                    None => PlanOptions {
+                        flag: analysis.flag.clone(),
+                        ..options.clone()
+                    },
+                };
+                entry.analysis = Some(analysis);
+
+                plan(&self.series(i, &entry), &options).await
+            }
+            .await;
+
+            match result {
+                Ok(plan) => entry.plan = Some(plan),
+                Err(Error::Cancelled) => return Err(Error::Cancelled),
+                Err(e) => entry.error = Some(ErrorReport::from(&e)),
+            }
+
+            self.entries[i] = entry;
+        }
+
+        Ok(self)
+    }
+
+    // Converts the planned series as a queue, one series after another.
+    // Series that were not bundled or fail as a whole are reported without stopping the queue.
+    pub async fn convert(&self, options: &ConvertOptions) -> Result<LibraryReport, Error> {
+        let mut report = LibraryReport {
+            converted: 0,
+            failed: 0,
+            skipped: 0,
+            volumes: 0,
+            series: Vec::with_capacity(self.entries.len()),
+        };
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let mut series_report = SeriesReport {
+                name: entry.name.clone(),
+                source: entry.source.clone(),
+                skipped: entry.skip,
+                conversion: None,
+                error: None,
+            };
+
+            if entry.skip {
+                report.skipped += 1;
+                report.series.push(series_report);
+                continue;
+            }
+
+            let result = match (&entry.plan, &entry.error) {
+                (_, Some(error)) => Err(error.clone()),
+                (None, None) => Err(ErrorReport::from(&Error::Unsupported(
+                    "Converting a series that has not been bundled".to_string(),
+                ))),
+                (Some(plan), None) => match convert(&self.series(i, entry), plan, options).await {
+                    Ok(conversion) => Ok(conversion),
+                    Err(Error::Cancelled) => return Err(Error::Cancelled),
+                    Err(e) => Err(ErrorReport::from(&e)),
+                },
+            };
+
+            match result {
+                Ok(conversion) => {
+                    match conversion.failed {
+                        0 => report.converted += 1,
+                        _ => report.failed += 1,
+                    }
+                    report.volumes += conversion.volumes.len() - conversion.failed;
+                    series_report.conversion = Some(conversion);
+                }
+                Err(error) => {
+                    report.failed += 1;
+                    series_report.error = Some(error);
+                }
+            }
+
+            report.series.push(series_report);
+        }
+
+        Ok(report)
+    }
+}
+
+// Whether a directory contains at least one chapter, i.e. a directory or an archive
+async fn has_chapters(directory: &Path) -> bool {
+    let mut paths = match read_dir(directory).await {
+        Ok(paths) => paths,
+        Err(_) => return false,
+    };
+
+    while let Ok(Some(path)) = paths.next_entry().await {
+        if !path.file_name().to_string_lossy().starts_with('.') && (path.path().is_dir() || archive::is_archive(&path.path())) {
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::collector::Collector;
+use crate::prelude::*;
+
+lazy_static! {
+    // e.g. "002-032" or "002-032 - Title"
+    static ref RE_DASHED: Regex = Regex::new(
+        r"^(?P<volume>\d+)-(?P<chapter>\d+(?:\.\d+)?)(?:\s*-\s*(?P<title>.*))?$"
+    ).unwrap();
+    // e.g. "Vol.02 Ch.032 - Title" or "Volume 2 Chapter 32"
+    static ref RE_LABELED: Regex = Regex::new(
+        r"(?i)^vol(?:ume)?\.?\s*(?P<volume>\d+)\s*ch(?:apter)?\.?\s*(?P<chapter>\d+(?:\.\d+)?)(?:\s*-\s*(?P<title>.*))?$"
+    ).unwrap();
+    // e.g. "v02c032" or "v02c032 Title"
+    static ref RE_COMPACT: Regex = Regex::new(
+        r"(?i)^v(?P<volume>\d+)\s*c(?P<chapter>\d+(?:\.\d+)?)(?:\s*-?\s*(?P<title>.*))?$"
+    ).unwrap();
+    // e.g. "Group_Vol.2 Ch.32 - Title" (Mihon downloads), chapters without a volume are allowed
+    static ref RE_MIHON: Regex = Regex::new(
+        r"(?i)^(?:[^_]*_)?(?:vol\.?\s*(?P<volume>\d+)\s*)?ch\.?\s*(?P<chapter>\d+(?:\.\d+)?)(?:\s*-\s*(?P<title>.*))?$"
+    ).unwrap();
+}
+
+// The naming conventions used to determine the volume and chapter of a chapter directory or archive
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum NameTemplate {
+    #[default]
+    DASHED,
+    LABELED,
+    COMPACT,
+    MIHON,
+    // A user-supplied regex with the named groups `volume`, `chapter` and `title`
+    CUSTOM(String),
+}
+
+impl NameTemplate {
+    pub const PRESETS: [NameTemplate; 4] = [
+        NameTemplate::DASHED,
+        NameTemplate::LABELED,
+        NameTemplate::COMPACT,
+        NameTemplate::MIHON,
+    ];
+
+    // Returns the first template that is able to parse every chapter and finds at least one volume.
+    // A custom template is preferred over the presets.
+    pub fn detect(chapters: &[PathBuf], preferred: &NameTemplate) -> Option<NameTemplate> {
+        let custom = match preferred {
+            NameTemplate::CUSTOM(_) => Some(preferred.clone()),
+            _ => None,
+        };
+
+        custom
+            .into_iter()
+            .chain(Self::PRESETS)
+            .find(|template| match NameParser::new(template) {
+                Ok(parser) => {
+                    let names: Vec<Option<ChapterName>> =
+                        chapters.iter().map(|chapter| parser.parse(chapter)).collect();
+
+                    names.iter().all(Option::is_some)
+                        && names.iter().flatten().any(|name| name.volume.is_some())
+                }
+                Err(_) => false,
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChapterName {
+    pub volume: Option<usize>,
+    pub chapter: Option<f64>,
+    pub title: Option<String>,
+}
+
+impl ChapterName {
+    // The name in the `VOLUME-CHAPTER` convention, e.g. "002-032" or "002-032.5 - Title"
+    pub fn dashed(&self) -> Option<String> {
+        let chapter = self.chapter?;
+        let fraction = chapter
+            .to_string()
+            .split_once('.')
+            .map(|(_, fraction)| format!(".{}", fraction))
+            .unwrap_or_default();
+        let name = format!("{:03}-{:03}{}", self.volume?, chapter.trunc() as u64, fraction);
+
+        Some(match &self.title {
+            Some(title) => format!("{} - {}", name, title),
+            None => name,
+        })
+    }
+}
+
+pub struct NameParser {
+    regex: Regex,
+}
+
+impl NameParser {
+    pub fn new(template: &NameTemplate) -> Result<Self, Error> {
+        let regex = match template {
+            NameTemplate::DASHED => RE_DASHED.clone(),
+            NameTemplate::LABELED => RE_LABELED.clone(),
+            NameTemplate::COMPACT => RE_COMPACT.clone(),
+            NameTemplate::MIHON => RE_MIHON.clone(),
+            NameTemplate::CUSTOM(pattern) => {
+                let regex = Regex::new(pattern)?;
+
+                // Without a volume, the template can't be used for bundling
+                if !regex.capture_names().flatten().any(|name| name == "volume") {
+                    return Err(Error::Unsupported(
+                        "Custom name templates without a named 'volume' group".to_string(),
+                    ));
+                }
+
+                regex
+            }
+        };
+
+        Ok(Self { regex })
+    }
+
+    pub fn parse(&self, path: &Path) -> Option<ChapterName> {
+        let captures = self.regex.captures(Collector::chapter_name(path)?.trim())?;
+
+        Some(ChapterName {
+            volume: captures
+                .name("volume")
+                .and_then(|volume| volume.as_str().parse::<usize>().ok()),
+            chapter: captures
+                .name("chapter")
+                .and_then(|chapter| chapter.as_str().parse::<f64>().ok()),
+            title: captures
+                .name("title")
+                .map(|title| title.as_str().trim().to_string())
+                .filter(|title| !title.is_empty()),
+        })
+    }
+
+    // Compares chapters by their volume first and their chapter second.
+    // Chapters without a volume are sorted after all others.
+    pub fn compare(&self, a: &PathBuf, b: &PathBuf) -> Ordering {
+        let key = |path: &PathBuf| -> (usize, f64) {
+            self.parse(path).map_or((usize::MAX, f64::MAX), |name| {
+                (
+                    name.volume.unwrap_or(usize::MAX),
+                    name.chapter.unwrap_or(f64::MAX),
+                )
+            })
+        };
+
+        let (av, ac) = key(a);
+        let (bv, bc) = key(b);
+
+        av.cmp(&bv)
+            .then_with(|| ac.partial_cmp(&bc).unwrap_or(Ordering::Equal))
+            .then_with(|| Collector::sort_natural(a, b))
+    }
+}
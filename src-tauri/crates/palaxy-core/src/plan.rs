@@ -0,0 +1,416 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::Collector;
+use crate::duplicates::{self, DuplicateChoice, DuplicatePolicy};
+use crate::naming::{NameParser, NameTemplate};
+use crate::prelude::*;
+use crate::series::Series;
+
+// How the chapters of a series are bundled into volumes
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PlanOptions {
+    pub flag: BundleFlag,
+    // Sensibility of the image detection in percent
+    pub sensibility: Option<usize>,
+    // Pages per volume and the allowed deviation for `BundleFlag::PAGES`
+    pub page_target: Option<usize>,
+    pub page_tolerance: Option<usize>,
+    // Detectors for `BundleFlag::IMAGE`, only the color detector is used if none are given
+    pub detectors: Option<Vec<DetectorKind>>,
+    // Chapters per volume for `BundleFlag::MANUAL`
+    pub sizes: Option<Vec<usize>>,
+    // Without a policy, chapters with the same number are all bundled
+    pub duplicates: Option<DuplicatePolicy>,
+}
+
+// The pages of every chapter and the number of chapters in every volume.
+// Without volume sizes (e.g. for `BundleFlag::MANUAL`), they have to be given before converting.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Plan {
+    pub pages: Vec<Vec<PathBuf>>,
+    pub volume_sizes: Vec<usize>,
+    // The result of the cover detection, only available for `BundleFlag::IMAGE`
+    pub covers: Vec<CoverScore>,
+    // The chapters with the same number and which of them were bundled, only available with a duplicate policy
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateChoice>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlanIssueKind {
+    NoVolumes,
+    // The volume sizes do not add up to the number of chapters
+    SizeMismatch,
+    EmptyVolume,
+    EmptyChapter,
+    MissingFile,
+    UnsupportedFormat,
+}
+
+// A problem that prevents the plan from being converted. Volumes and chapters are counted from 1.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlanIssue {
+    pub kind: PlanIssueKind,
+    pub volume: Option<usize>,
+    pub chapter: Option<usize>,
+    pub file: Option<PathBuf>,
+    pub message: String,
+}
+
+impl Plan {
+    pub fn total_chapters(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn total_volumes(&self) -> usize {
+        self.volume_sizes.len()
+    }
+
+    // Checks that every volume can be converted, without reading any of the images
+    pub fn validate(&self) -> Vec<PlanIssue> {
+        let mut issues = Vec::new();
+        let total: usize = self.volume_sizes.iter().sum();
+
+        if self.volume_sizes.is_empty() {
+            issues.push(PlanIssue {
+                kind: PlanIssueKind::NoVolumes,
+                volume: None,
+                chapter: None,
+                file: None,
+                message: "The plan has no volumes".to_string(),
+            });
+        } else if total != self.pages.len() {
+            issues.push(PlanIssue {
+                kind: PlanIssueKind::SizeMismatch,
+                volume: None,
+                chapter: None,
+                file: None,
+                message: format!(
+                    "The volumes contain {} chapters, but there are {}",
+                    total,
+                    self.pages.len()
+                ),
+            });
+        }
+
+        // The volume of every chapter, chapters outside of all volumes have none
+        let volumes: Vec<Option<usize>> = self
+            .volume_sizes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, size)| std::iter::repeat(Some(i + 1)).take(*size))
+            .chain(std::iter::repeat(None))
+            .take(self.pages.len())
+            .collect();
+
+        for (i, size) in self.volume_sizes.iter().enumerate() {
+            if *size == 0 {
+                issues.push(PlanIssue {
+                    kind: PlanIssueKind::EmptyVolume,
+                    volume: Some(i + 1),
+                    chapter: None,
+                    file: None,
+                    message: format!("Volume {} has no chapters", i + 1),
+                });
+            }
+        }
+
+        for (i, pages) in self.pages.iter().enumerate() {
+            if pages.is_empty() {
+                issues.push(PlanIssue {
+                    kind: PlanIssueKind::EmptyChapter,
+                    volume: volumes[i],
+                    chapter: Some(i + 1),
+                    file: None,
+                    message: format!("Chapter {} has no pages", i + 1),
+                });
+            }
+        }
+
+        // Pages are checked in parallel, as large series have thousands of them
+        let mut page_issues: Vec<PlanIssue> = self
+            .pages
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, pages)| pages.iter().map(move |page| (i, page)))
+            .filter_map(|(i, page)| {
+                let (kind, message) = if !archive::is_file(page) {
+                    (PlanIssueKind::MissingFile, format!("Page {:?} does not exist", page))
+                } else if let Err(e) = get_file_info(page) {
+                    (PlanIssueKind::UnsupportedFormat, format!("Page {:?}: {}", page, e))
+                } else {
+                    return None;
+                };
+
+                Some(PlanIssue {
+                    kind,
+                    volume: volumes[i],
+                    chapter: Some(i + 1),
+                    file: Some(page.clone()),
+                    message,
+                })
+            })
+            .collect();
+
+        issues.append(&mut page_issues);
+        issues
+    }
+
+    // Fails with the first issue of the plan, so nothing is written for an invalid plan
+    pub fn check(&self) -> Result<(), Error> {
+        let issues = self.validate();
+
+        let issue = match issues.first() {
+            Some(issue) => issue,
+            None => return Ok(()),
+        };
+        let mut error = Error::InvalidPlan(match issues.len() {
+            1 => issue.message.clone(),
+            count => format!("{} (and {} more issues)", issue.message, count - 1),
+        });
+
+        if let Some(path) = &issue.file {
+            error = error.with_path(path);
+        }
+        if let Some(chapter) = issue.chapter {
+            error = error.with_chapter(chapter);
+        }
+        if let Some(volume) = issue.volume {
+            error = error.with_volume(volume);
+        }
+
+        Err(error)
+    }
+}
+
+pub async fn plan(series: &Series, options: &PlanOptions) -> Result<Plan, Error> {
+    let mut collector = series.collector();
+
+    // Nested layouts are collected per volume directory, all others as a flat list of chapters
+    let mut volumes: Vec<Vec<PathBuf>> = match options.flag {
+        BundleFlag::FOLDER => collector
+            .collect_volumes(Some(&Collector::sort_natural))
+            .await?
+            .into_iter()
+            .filter(|chapters| !chapters.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // Collect all pages and sort based on the bundle flag
+    let mut chapters: Vec<PathBuf> = match options.flag {
+        BundleFlag::FOLDER => volumes.concat(),
+        _ => collector
+            .collect_chapters(Some(&Collector::sort_natural))
+            .await?,
+    };
+
+    // Chapters bundled by name are ordered by their volume and chapter number instead
+    if options.flag == BundleFlag::NAME {
+        let parser = NameParser::new(&series.name_template)?;
+        chapters.par_sort_by(|a, b| parser.compare(a, b));
+    }
+
+    let mut pages: Vec<Vec<PathBuf>> = collector
+        .collect_pages(chapters.clone(), Some(&Collector::sort_natural))
+        .await?;
+
+    // Rejected duplicates are removed before the volumes are determined, as if they were never collected
+    let duplicates = match &options.duplicates {
+        Some(policy) => {
+            // Chapters of different volumes are never duplicates, so the volume is parsed for every flag
+            let template = match options.flag {
+                BundleFlag::NAME => Some(series.name_template.clone()),
+                _ => NameTemplate::detect(&chapters, &series.name_template),
+            };
+            let parser = template.map(|template| NameParser::new(&template)).transpose()?;
+            let presets = NameTemplate::PRESETS
+                .iter()
+                .map(NameParser::new)
+                .collect::<Result<Vec<NameParser>, Error>>()?;
+            let numbers: Vec<Option<(Option<usize>, f64)>> = chapters
+                .iter()
+                .map(|chapter| {
+                    let name = parser.as_ref().and_then(|parser| parser.parse(chapter));
+                    let number = name
+                        .as_ref()
+                        .and_then(|name| name.chapter)
+                        .or_else(|| Collector::chapter_number(chapter))?;
+                    let volume = match options.flag {
+                        BundleFlag::FOLDER => volumes.iter().position(|volume| volume.contains(chapter)),
+                        // Without a template for every chapter, any preset that finds a volume is used
+                        _ => name.and_then(|name| name.volume).or_else(|| {
+                            presets.iter().find_map(|parser| parser.parse(chapter).and_then(|name| name.volume))
+                        }),
+                    };
+
+                    Some((volume, number))
+                })
+                .collect();
+
+            duplicates::resolve(&chapters, &pages, &numbers, policy)
+        }
+        None => Vec::new(),
+    };
+    let rejected: HashSet<&PathBuf> = duplicates.iter().flat_map(|choice| &choice.rejected).collect();
+
+    if !rejected.is_empty() {
+        let (kept_chapters, kept_pages) = chapters
+            .iter()
+            .cloned()
+            .zip(pages)
+            .filter(|(chapter, _)| !rejected.contains(chapter))
+            .unzip();
+        chapters = kept_chapters;
+        pages = kept_pages;
+
+        for volume in volumes.iter_mut() {
+            volume.retain(|chapter| !rejected.contains(chapter));
+        }
+        volumes.retain(|volume| !volume.is_empty());
+    }
+
+    let total_chapters: usize = chapters.len();
+    let mut chapter_sizes: Vec<usize> = Vec::default();
+    let mut covers: Vec<CoverScore> = Vec::default();
+
+    match options.flag {
+        // For manual bundling, the user has to input the sizes, either up front or after bundling.
+        BundleFlag::MANUAL => {
+            chapter_sizes = options.sizes.clone().unwrap_or_default();
+        }
+        // For automatic bundling by name,
+        // the program will use the naming convention
+        // to determine the volumes and chapters.
+        BundleFlag::NAME => {
+            let parser = NameParser::new(&series.name_template)?;
+
+            // A new volume starts whenever the volume number changes
+            let mut previous_volume = None;
+            for (i, chapter) in chapters.iter().enumerate() {
+                let volume_number = parser.parse(chapter).and_then(|name| name.volume);
+
+                if i == 0 || volume_number != previous_volume {
+                    chapter_sizes.push(0);
+                }
+
+                if let Some(size) = chapter_sizes.last_mut() {
+                    *size += 1;
+                }
+
+                previous_volume = volume_number;
+            }
+        }
+        // The image version determines the start of each volume by a colored cover. Other detectors
+        // (title pages, blank pages, ...) can be combined with it, but are only used if they are given.
+        BundleFlag::IMAGE => {
+            let cover_scores = collector
+                .determine_volume_start_chapters(
+                    chapters.clone(),
+                    pages.clone(),
+                    options.sensibility.map_or(0.75, |s| s as f64 / 100.0),
+                    options
+                        .detectors
+                        .clone()
+                        .unwrap_or_else(|| vec![DetectorKind::COLOR]),
+                )
+                .await?;
+
+            let volume_start_chapters: Vec<usize> = cover_scores
+                .iter()
+                .filter(|cover_score| cover_score.volume_start)
+                .map(|cover_score| cover_score.chapter)
+                .collect();
+            covers = cover_scores;
+
+            chapter_sizes =
+                collector.calculate_volume_sizes(volume_start_chapters, total_chapters)?;
+        }
+        // The page version groups whole chapters into volumes of roughly the same page count.
+        // Without a given tolerance, volumes may deviate by 10% of the target.
+        BundleFlag::PAGES => {
+            let target = options.page_target.unwrap_or(200);
+
+            chapter_sizes = collector.calculate_volume_sizes_by_pages(
+                &pages,
+                target,
+                options.page_tolerance.unwrap_or(target / 10),
+            )?;
+        }
+        // The folder version takes the volumes directly from the volume directories,
+        // so neither the names nor the images have to be inspected.
+        BundleFlag::FOLDER => {
+            chapter_sizes = volumes.iter().map(|chapters| chapters.len()).collect();
+        }
+    };
+
+    Ok(Plan {
+        pages,
+        volume_sizes: chapter_sizes,
+        covers,
+        duplicates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn kinds(plan: &Plan) -> Vec<PlanIssueKind> {
+        plan.validate().iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn validate_checks_the_volume_sizes() {
+        let pages = vec![vec![PathBuf::from("/missing/1.png")]; 2];
+        let plan = |volume_sizes: Vec<usize>| Plan {
+            pages: pages.clone(),
+            volume_sizes,
+            ..Plan::default()
+        };
+
+        assert!(kinds(&plan(vec![])).contains(&PlanIssueKind::NoVolumes));
+        assert!(kinds(&plan(vec![1])).contains(&PlanIssueKind::SizeMismatch));
+        assert!(kinds(&plan(vec![2, 0])).contains(&PlanIssueKind::EmptyVolume));
+    }
+
+    #[test]
+    fn validate_checks_every_page() {
+        let directory = std::env::temp_dir().join(format!("palaxy-plan-validate-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let page = directory.join("1.png");
+        let text = directory.join("2.txt");
+        fs::write(&page, b"").unwrap();
+        fs::write(&text, b"").unwrap();
+
+        let plan = Plan {
+            pages: vec![vec![page.clone()], vec![], vec![text, directory.join("3.png")]],
+            volume_sizes: vec![1, 2],
+            ..Plan::default()
+        };
+        let valid = Plan {
+            pages: vec![vec![page]],
+            volume_sizes: vec![1],
+            ..Plan::default()
+        };
+        let issues = plan.validate();
+        let checked = valid.check();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(checked.is_ok());
+
+        let issues: Vec<(PlanIssueKind, Option<usize>, Option<usize>)> =
+            issues.iter().map(|issue| (issue.kind, issue.volume, issue.chapter)).collect();
+        assert_eq!(issues.len(), 3);
+        assert!(issues.contains(&(PlanIssueKind::EmptyChapter, Some(2), Some(2))));
+        assert!(issues.contains(&(PlanIssueKind::UnsupportedFormat, Some(2), Some(3))));
+        assert!(issues.contains(&(PlanIssueKind::MissingFile, Some(2), Some(3))));
+    }
+}
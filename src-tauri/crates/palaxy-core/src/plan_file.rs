@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::Collector;
+use crate::plan::Plan;
+use crate::prelude::*;
+use crate::series::Series;
+
+// The chapters of a single volume, addressed by their path relative to the source
+// (e.g. `001-002` or `Volume 01/Chapter 02.cbz` for nested layouts)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlanVolume {
+    pub volume: usize,
+    pub chapters: Vec<String>,
+}
+
+// A plan that can be edited in a spreadsheet or generated by scripts and imported again
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlanFile {
+    pub volumes: Vec<PlanVolume>,
+}
+
+// The imported plan and the chapters of the source that are not part of it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlanImport {
+    pub plan: Plan,
+    pub omitted: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum PlanFormat {
+    JSON,
+    CSV,
+}
+
+impl PlanFormat {
+    // Files without a known extension are treated as JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => PlanFormat::CSV,
+            _ => PlanFormat::JSON,
+        }
+    }
+}
+
+// The name of a chapter relative to the source, always separated by `/`
+fn chapter_name(source: &Path, chapter: &Path) -> String {
+    chapter
+        .strip_prefix(source)
+        .unwrap_or(chapter)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl PlanFile {
+    pub fn from_plan(plan: &Plan, source: &Path) -> Result<Self, Error> {
+        if plan.volume_sizes.is_empty() {
+            return Err(Error::InvalidPlan("The plan has no volumes".to_string()));
+        }
+        if plan.volume_sizes.iter().sum::<usize>() != plan.pages.len() {
+            return Err(Error::InvalidPlan(format!(
+                "The volumes contain {} chapters, but the plan has {}",
+                plan.volume_sizes.iter().sum::<usize>(),
+                plan.pages.len()
+            )));
+        }
+
+        let mut chapters = plan.pages.iter().enumerate().map(|(i, pages)| {
+            pages
+                .first()
+                .and_then(|page| archive::chapter(page))
+                .map(|chapter| chapter_name(source, &chapter))
+                .ok_or_else(|| Error::InvalidPlan(format!("Chapter {} has no pages", i + 1)))
+        });
+
+        let mut volumes = Vec::with_capacity(plan.volume_sizes.len());
+        for (i, size) in plan.volume_sizes.iter().enumerate() {
+            volumes.push(PlanVolume {
+                volume: i + 1,
+                chapters: chapters.by_ref().take(*size).collect::<Result<_, _>>()?,
+            });
+        }
+
+        Ok(Self { volumes })
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // One row per chapter, quoted where needed so names with separators survive spreadsheets
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("volume,chapter\n");
+
+        for volume in &self.volumes {
+            for chapter in &volume.chapters {
+                let chapter = match chapter.contains(&[',', ';', '"', '\n', '\r'][..]) {
+                    true => format!("\"{}\"", chapter.replace('"', "\"\"")),
+                    false => chapter.clone(),
+                };
+                csv.push_str(&format!("{},{}\n", volume.volume, chapter));
+            }
+        }
+
+        csv
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    // Rows of the same volume are grouped, the volumes are ordered by their number
+    pub fn from_csv(csv: &str) -> Result<Self, Error> {
+        let mut volumes: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+        for (i, row) in parse_csv(csv)?.into_iter().enumerate() {
+            let (volume, chapter) = match row.as_slice() {
+                [volume, chapter] => (volume.trim(), chapter.clone()),
+                [volume] if volume.trim().is_empty() => continue,
+                _ => {
+                    return Err(Error::InvalidPlan(format!(
+                        "Row {} has to contain a volume and a chapter",
+                        i + 1
+                    )))
+                }
+            };
+
+            // The header is optional
+            if i == 0 && volume.eq_ignore_ascii_case("volume") {
+                continue;
+            }
+
+            let volume = volume.parse().map_err(|_| {
+                Error::InvalidPlan(format!("Row {} has an invalid volume '{}'", i + 1, volume))
+            })?;
+            volumes.entry(volume).or_default().push(chapter);
+        }
+
+        Ok(Self {
+            volumes: volumes
+                .into_iter()
+                .map(|(volume, chapters)| PlanVolume { volume, chapters })
+                .collect(),
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let content = match PlanFormat::from_path(path) {
+            PlanFormat::JSON => self.to_json()?,
+            PlanFormat::CSV => self.to_csv(),
+        };
+
+        Ok(fs::write(path, content)?)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)?;
+
+        match PlanFormat::from_path(path) {
+            PlanFormat::JSON => Self::from_json(&content),
+            PlanFormat::CSV => Self::from_csv(&content),
+        }
+    }
+
+    // Resolves the chapters against the chapters the collector finds in the source.
+    // Chapters may be reordered, but every chapter has to exist and may only be used once.
+    pub async fn import(&self, series: &Series) -> Result<PlanImport, Error> {
+        let mut collector = series.collector();
+
+        // Like `analyze`, nested layouts are only assumed if every volume directory contains chapters
+        let chapters = match collector.collect_volumes(None).await {
+            Ok(volumes) if !volumes.is_empty() && volumes.iter().all(|chapters| !chapters.is_empty()) => {
+                volumes.concat()
+            }
+            Err(Error::Cancelled) => return Err(Error::Cancelled),
+            _ => collector.collect_chapters(None).await?,
+        };
+        let mut known: HashMap<String, PathBuf> = chapters
+            .into_iter()
+            .map(|chapter| (chapter_name(&series.source, &chapter), chapter))
+            .collect();
+
+        let mut used: HashSet<&str> = HashSet::new();
+        let mut missing: Vec<&str> = Vec::new();
+        let mut plan_chapters: Vec<PathBuf> = Vec::new();
+        let mut volume_sizes: Vec<usize> = Vec::new();
+
+        for volume in self.volumes.iter().filter(|volume| !volume.chapters.is_empty()) {
+            for chapter in &volume.chapters {
+                let name = chapter.trim();
+
+                if !used.insert(name) {
+                    return Err(Error::InvalidPlan(format!("Chapter '{}' is used more than once", name)));
+                }
+
+                match known.remove(name) {
+                    Some(path) => plan_chapters.push(path),
+                    None => missing.push(name),
+                }
+            }
+
+            volume_sizes.push(volume.chapters.len());
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::InvalidPlan(format!(
+                "Chapters not found in the source: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let pages = collector
+            .collect_pages(plan_chapters, Some(&Collector::sort_natural))
+            .await?;
+        let mut omitted: Vec<String> = known.into_keys().collect();
+        omitted.sort();
+
+        Ok(PlanImport {
+            plan: Plan {
+                pages,
+                volume_sizes,
+                covers: Vec::new(),
+                duplicates: Vec::new(),
+            },
+            omitted,
+        })
+    }
+}
+
+// Splits CSV into rows of fields, supporting quoted fields with escaped quotes and line breaks.
+// Semicolons are accepted as separator as well, as some spreadsheets use them depending on the locale.
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') | (false, ';') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(Error::InvalidPlan("Unterminated quote in CSV".to_string()));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_handles_quotes_and_separators() {
+        let rows = parse_csv("\u{feff}volume;chapter\r\n1,\"Ch. 1, \"\"Start\"\"\"\n2,\"multi\nline\"").unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["volume".to_string(), "chapter".to_string()],
+                vec!["1".to_string(), "Ch. 1, \"Start\"".to_string()],
+                vec!["2".to_string(), "multi\nline".to_string()],
+            ]
+        );
+        assert!(parse_csv("1,\"unterminated").is_err());
+    }
+
+    #[test]
+    fn plan_file_round_trips_through_csv_and_json() {
+        let file = PlanFile {
+            volumes: vec![
+                PlanVolume {
+                    volume: 1,
+                    chapters: vec!["001-001".to_string(), "Volume 01/Chapter, \"2\".cbz".to_string()],
+                },
+                PlanVolume {
+                    volume: 2,
+                    chapters: vec!["002-003".to_string()],
+                },
+            ],
+        };
+        let chapters = |file: &PlanFile| -> Vec<(usize, Vec<String>)> {
+            file.volumes
+                .iter()
+                .map(|volume| (volume.volume, volume.chapters.clone()))
+                .collect()
+        };
+
+        assert_eq!(chapters(&PlanFile::from_csv(&file.to_csv()).unwrap()), chapters(&file));
+        assert_eq!(chapters(&PlanFile::from_json(&file.to_json().unwrap()).unwrap()), chapters(&file));
+    }
+
+    #[test]
+    fn from_csv_groups_rows_by_volume() {
+        let file = PlanFile::from_csv("2,b\n1,a\n2,c\n\n").unwrap();
+
+        assert_eq!(file.volumes.len(), 2);
+        assert_eq!(file.volumes[0].chapters, vec!["a"]);
+        assert_eq!(file.volumes[1].chapters, vec!["b", "c"]);
+        assert!(PlanFile::from_csv("one,a").is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use printpdf::image_crate;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Error types
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[error(transparent)]
+    Epub(#[from] eyre::Report),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    SevenZ(#[from] sevenz_rust::Error),
+    #[error(transparent)]
+    PrintPdf(#[from] printpdf::Error),
+    #[error(transparent)]
+    PrintPdfImage(#[from] image_crate::error::ImageError),
+    #[error("The given path '{0}' is invalid: {1}")]
+    InvalidPath(PathBuf, String),
+    #[error("Asynchronous task failed: {0}")]
+    AsyncTaskError(String),
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Invalid plan: {0}")]
+    InvalidPlan(String),
+    #[error("Conversion failed: {0}")]
+    ConversionFailed(String),
+    #[error("The process was cancelled")]
+    Cancelled,
+    // Adds the file, chapter or volume an error occurred at, without changing its message or code
+    #[error("{error}")]
+    Context {
+        error: Box<Error>,
+        context: ErrorContext,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ErrorContext {
+    pub path: Option<PathBuf>,
+    // Counted from 1
+    pub chapter: Option<usize>,
+    pub volume: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub enum ErrorCategory {
+    // Reading or writing files and directories
+    FILESYSTEM,
+    // Invalid settings, templates, plans or project files
+    INPUT,
+    // Decoding the images of the pages
+    IMAGE,
+    // Reading archived chapters
+    ARCHIVE,
+    // Writing the converted volumes
+    OUTPUT,
+    // Failed background tasks and conversions
+    PROCESS,
+    CANCELLED,
+}
+
+impl Error {
+    // The context is only set where it is still unknown, so the most precise location is kept.
+    // Cancellations are never wrapped, as they are matched on to stop processes.
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            Error::Cancelled => Error::Cancelled,
+            Error::Context { error, mut context } => {
+                update(&mut context);
+                Error::Context { error, context }
+            }
+            error => {
+                let mut context = ErrorContext::default();
+                update(&mut context);
+                Error::Context {
+                    error: Box::new(error),
+                    context,
+                }
+            }
+        }
+    }
+
+    pub fn with_path(self, path: &Path) -> Self {
+        self.with_context(|context| {
+            context.path.get_or_insert_with(|| path.to_path_buf());
+        })
+    }
+
+    pub fn with_chapter(self, chapter: usize) -> Self {
+        self.with_context(|context| {
+            context.chapter.get_or_insert(chapter);
+        })
+    }
+
+    pub fn with_volume(self, volume: usize) -> Self {
+        self.with_context(|context| {
+            context.volume.get_or_insert(volume);
+        })
+    }
+
+    // The error without its context
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Context { error, .. } => error.kind(),
+            error => error,
+        }
+    }
+
+    pub fn context(&self) -> ErrorContext {
+        match self {
+            Error::Context { context, .. } => context.clone(),
+            Error::InvalidPath(path, _) => ErrorContext {
+                path: Some(path.clone()),
+                ..ErrorContext::default()
+            },
+            _ => ErrorContext::default(),
+        }
+    }
+
+    // A stable identifier, which the frontend can react to instead of parsing the message
+    pub fn code(&self) -> &'static str {
+        match self.kind() {
+            Error::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => "FILE_NOT_FOUND",
+                std::io::ErrorKind::PermissionDenied => "PERMISSION_DENIED",
+                _ => "IO",
+            },
+            Error::Regex(_) => "INVALID_TEMPLATE",
+            Error::Image(_) => "IMAGE",
+            Error::Epub(_) => "EPUB",
+            Error::Json(_) => "INVALID_FILE",
+            Error::Zip(_) => "ZIP",
+            Error::SevenZ(_) => "SEVEN_ZIP",
+            Error::PrintPdf(_) => "PDF",
+            Error::PrintPdfImage(_) => "PDF_IMAGE",
+            Error::InvalidPath(..) => "INVALID_PATH",
+            Error::AsyncTaskError(_) => "TASK_FAILED",
+            Error::Unsupported(_) => "UNSUPPORTED",
+            Error::NotFound(_) => "NOT_FOUND",
+            Error::InvalidPlan(_) => "INVALID_PLAN",
+            Error::ConversionFailed(_) => "CONVERSION_FAILED",
+            Error::Cancelled => "CANCELLED",
+            Error::Context { .. } => unreachable!("the kind of an error has no context"),
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self.kind() {
+            Error::Io(_) | Error::InvalidPath(..) => ErrorCategory::FILESYSTEM,
+            Error::Regex(_)
+            | Error::Json(_)
+            | Error::Unsupported(_)
+            | Error::NotFound(_)
+            | Error::InvalidPlan(_) => ErrorCategory::INPUT,
+            Error::Image(_) | Error::PrintPdfImage(_) => ErrorCategory::IMAGE,
+            Error::Zip(_) | Error::SevenZ(_) => ErrorCategory::ARCHIVE,
+            Error::Epub(_) | Error::PrintPdf(_) => ErrorCategory::OUTPUT,
+            Error::AsyncTaskError(_) | Error::ConversionFailed(_) => ErrorCategory::PROCESS,
+            Error::Cancelled => ErrorCategory::CANCELLED,
+            Error::Context { .. } => unreachable!("the kind of an error has no context"),
+        }
+    }
+}
+
+// The serialized form of an error, which is also kept in reports after the error itself is gone
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorReport {
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    pub chapter: Option<usize>,
+    pub volume: Option<usize>,
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(error: &Error) -> Self {
+        let context = error.context();
+
+        Self {
+            code: error.code().to_string(),
+            category: error.category(),
+            message: error.to_string(),
+            path: context.path,
+            chapter: context.chapter,
+            volume: context.volume,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        ErrorReport::from(self).serialize(serializer)
+    }
+}
+
+pub type EResult<T> = Result<T, Error>;
+
+// The result of the cover detection for a single chapter, used to review the detected volume starts
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CoverScore {
+    pub chapter: usize,
+    pub cover: PathBuf,
+    pub colorfulness: f64,
+    // The weighted score of all detectors
+    pub score: f64,
+    pub confidence: f64,
+    pub volume_start: bool,
+    pub detectors: Vec<DetectorScore>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DetectorScore {
+    pub detector: DetectorKind,
+    pub score: f64,
+}
+
+// Progress of a long running process, which is handed to the `ProgressReporter` of the caller
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Progress {
+    pub stage: Stage,
+    // Position of the series in a library, only set for batch conversions
+    pub series: Option<usize>,
+    pub volume: Option<usize>,
+    pub index: usize,
+    pub total: usize,
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum Stage {
+    // Collecting the pages of all chapters
+    COLLECT,
+    // Detecting the volume boundaries
+    DETECT,
+    // Adding the pages to the volumes
+    CONVERT,
+}
+
+pub type ProgressReporter = Arc<dyn Fn(Progress) + Send + Sync>;
+
+// Shared flag to stop running processes, which is checked before every chapter and page
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self) -> Result<(), Error> {
+        match self.is_cancelled() {
+            true => Err(Error::Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+// Types shared with the user interfaces
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub enum FileFormat {
+    PDF,
+    EPUB,
+    #[default]
+    CBZ,
+}
+
+impl FileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::PDF => "pdf",
+            FileFormat::EPUB => "epub",
+            FileFormat::CBZ => "cbz",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub enum Direction {
+    #[default]
+    #[serde(rename = "Left to Right")]
+    LTR,
+    #[serde(rename = "Right to Left")]
+    RTL,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VolumeReport {
+    pub volume: usize,
+    pub name: String,
+    pub success: bool,
+    pub path: Option<PathBuf>,
+    // File size in bytes
+    pub size: Option<u64>,
+    pub error: Option<ErrorReport>,
+}
+
+// Workflow types
+#[derive(Serialize, Deserialize, PartialEq, Clone, Default)]
+pub enum BundleFlag {
+    NAME,
+    IMAGE,
+    FOLDER,
+    PAGES,
+    #[default]
+    MANUAL,
+}
+
+// The strategies used to detect volume boundaries for `BundleFlag::IMAGE`
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub enum DetectorKind {
+    // Colored cover page
+    COLOR,
+    // Title page without margins
+    BLEED,
+    // Changed page size
+    SIZE,
+    // Large jump in the chapter numbers
+    JUMP,
+    // Blank page at the end of the previous chapter
+    BLANK,
+}
+
+impl DetectorKind {
+    pub const ALL: [DetectorKind; 5] = [
+        DetectorKind::COLOR,
+        DetectorKind::BLEED,
+        DetectorKind::SIZE,
+        DetectorKind::JUMP,
+        DetectorKind::BLANK,
+    ];
+}
+
+// Utils
+pub fn get_file_info(image_path: &PathBuf) -> Result<(&'static str, &'static str), Error> {
+    let path = image_path.extension().and_then(|e| e.to_str());
+
+    match path {
+        Some("jpg") | Some("jpeg") => Ok(("jpg", "image/jpeg")),
+        Some("png") => Ok(("png", "image/png")),
+        Some("webp") => Ok(("webp", "image/webp")),
+        _ => Err(Error::Unsupported(format!("Image format {:#?}", path)).with_path(image_path)),
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::convert::ConvertOptions;
+use crate::duplicates::{DuplicateChoice, DuplicatePolicy};
+use crate::naming::NameTemplate;
+use crate::plan::Plan;
+use crate::prelude::*;
+use crate::scheduler::ConvertLimits;
+use crate::series::Series;
+
+pub const PROJECT_EXTENSION: &str = "palaxy";
+// Increased whenever the format changes, so projects of newer versions are rejected instead of misread
+pub const PROJECT_VERSION: u32 = 2;
+
+// A saved series with its (manually tuned) plan, written as JSON to a `.palaxy` file.
+// The pages are stored relative to the source, so the project still works after the source was moved.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Project {
+    pub version: u32,
+    // Version of Palaxy that saved the project
+    pub palaxy: String,
+    // Seconds since the unix epoch
+    pub saved: u64,
+    pub source: PathBuf,
+    pub name: String,
+    pub name_template: NameTemplate,
+    pub flag: BundleFlag,
+    pub plan: Plan,
+    // The policy the chapters were bundled with, so the plan can be bundled again the same way
+    #[serde(default)]
+    pub duplicates: Option<DuplicatePolicy>,
+    pub convert: Option<ConvertOptions>,
+    // Projects of version 1 have no limits, which keeps the current ones
+    #[serde(default)]
+    pub limits: Option<ConvertLimits>,
+}
+
+// Where the source of a loaded project was found
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectSource {
+    pub source: PathBuf,
+    // The saved source, if the project was relocated
+    pub relocated: Option<PathBuf>,
+    // Pages of the plan (or their archives) that do not exist anymore
+    pub missing_pages: usize,
+}
+
+impl Project {
+    pub fn new(series: &Series, flag: BundleFlag, plan: &Plan, convert: Option<ConvertOptions>) -> Self {
+        let relative = |path: &PathBuf| {
+            path.strip_prefix(&series.source)
+                .map_or_else(|_| path.clone(), Path::to_path_buf)
+        };
+
+        Self {
+            version: PROJECT_VERSION,
+            palaxy: env!("CARGO_PKG_VERSION").to_string(),
+            saved: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            source: series.source.clone(),
+            name: series.name.clone(),
+            name_template: series.name_template.clone(),
+            flag,
+            plan: Plan {
+                pages: plan
+                    .pages
+                    .iter()
+                    .map(|chapter| chapter.iter().map(relative).collect())
+                    .collect(),
+                volume_sizes: plan.volume_sizes.clone(),
+                covers: plan
+                    .covers
+                    .iter()
+                    .cloned()
+                    .map(|cover| CoverScore {
+                        cover: relative(&cover.cover),
+                        ..cover
+                    })
+                    .collect(),
+                duplicates: plan
+                    .duplicates
+                    .iter()
+                    .map(|choice| DuplicateChoice {
+                        candidates: choice.candidates.iter().map(relative).collect(),
+                        chosen: choice.chosen.as_ref().map(relative),
+                        rejected: choice.rejected.iter().map(relative).collect(),
+                        ..choice.clone()
+                    })
+                    .collect(),
+            },
+            duplicates: None,
+            convert,
+            limits: None,
+        }
+    }
+
+    pub fn with_duplicates(mut self, duplicates: Option<DuplicatePolicy>) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ConvertLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    // Adds the extension if the path has none
+    pub fn save(&self, path: &Path) -> Result<PathBuf, Error> {
+        let path = match path.extension() {
+            Some(_) => path.to_path_buf(),
+            None => path.with_extension(PROJECT_EXTENSION),
+        };
+
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let project: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        if project.version > PROJECT_VERSION {
+            return Err(Error::Unsupported(format!(
+                "The project was saved by a newer version of Palaxy ({})",
+                project.palaxy
+            )));
+        }
+
+        Ok(project)
+    }
+
+    pub fn series(&self) -> Series {
+        Series::new(&self.source)
+            .with_name(&self.name)
+            .with_name_template(self.name_template.clone())
+    }
+
+    // The plan with the pages resolved against the current source
+    pub fn plan(&self) -> Plan {
+        Plan {
+            pages: self
+                .plan
+                .pages
+                .iter()
+                .map(|chapter| chapter.iter().map(|page| self.source.join(page)).collect())
+                .collect(),
+            volume_sizes: self.plan.volume_sizes.clone(),
+            covers: self
+                .plan
+                .covers
+                .iter()
+                .cloned()
+                .map(|cover| CoverScore {
+                    cover: self.source.join(&cover.cover),
+                    ..cover
+                })
+                .collect(),
+            duplicates: self
+                .plan
+                .duplicates
+                .iter()
+                .map(|choice| DuplicateChoice {
+                    candidates: choice.candidates.iter().map(|path| self.source.join(path)).collect(),
+                    chosen: choice.chosen.as_ref().map(|path| self.source.join(path)),
+                    rejected: choice.rejected.iter().map(|path| self.source.join(path)).collect(),
+                    ..choice.clone()
+                })
+                .collect(),
+        }
+    }
+
+    // Moves the project to another source. Without a given source, a moved source is looked for
+    // next to the project file, as series and their projects are usually moved together.
+    pub fn relocate(&mut self, path: &Path, source: Option<&Path>) -> Result<ProjectSource, Error> {
+        let saved = self.source.clone();
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+        self.source = match source {
+            Some(source) if source.is_dir() => source.to_path_buf(),
+            Some(source) => {
+                return Err(Error::InvalidPath(
+                    source.to_path_buf(),
+                    "The given source of the project does not exist".to_string(),
+                ))
+            }
+            None if saved.is_dir() => saved.clone(),
+            None => saved
+                .file_name()
+                .map(|name| directory.join(name))
+                .into_iter()
+                .chain(Some(directory.to_path_buf()))
+                .find(|candidate| candidate.is_dir() && candidate.file_name() == saved.file_name())
+                .ok_or_else(|| {
+                    Error::InvalidPath(saved.clone(), "The source of the project was moved".to_string())
+                })?,
+        };
+
+        Ok(ProjectSource {
+            source: self.source.clone(),
+            relocated: match self.source == saved {
+                true => None,
+                false => Some(saved),
+            },
+            missing_pages: self
+                .plan()
+                .pages
+                .iter()
+                .flatten()
+                .filter(|page| !archive::container(page).exists())
+                .count(),
+        })
+    }
+}
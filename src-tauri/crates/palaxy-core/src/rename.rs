@@ -0,0 +1,454 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::Collector;
+use crate::naming::{ChapterName, NameParser};
+use crate::plan::Plan;
+use crate::prelude::*;
+use crate::series::Series;
+
+// Written into the source, hidden so the collector ignores it
+pub const JOURNAL_NAME: &str = ".palaxy-renames.json";
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RenameOptions {
+    pub skip_chapters: bool,
+    pub skip_pages: bool,
+}
+
+// Renames inside of a source. Consecutive renames inside of the same directory are executed together,
+// so names can be swapped, later ones see the names of the earlier ones. They are applied as a whole or not at all.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenamePlan {
+    pub source: PathBuf,
+    pub renames: Vec<Rename>,
+}
+
+// The applied renames of a single plan, relative to the source
+#[derive(Serialize, Deserialize, Clone)]
+struct JournalEntry {
+    // Seconds since the unix epoch
+    applied: u64,
+    renames: Vec<Rename>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+// Renames and deletions may only touch paths inside of the source, which rules out relative components as well
+pub(crate) fn check_inside(path: &Path, source: &Path) -> Result<(), Error> {
+    let escapes = path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir));
+
+    if escapes || !path.starts_with(source) || path == source {
+        return Err(Error::InvalidPath(
+            path.to_path_buf(),
+            "Only paths inside of the source can be changed".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Proposes names from the order of a plan: chapter directories and archives are named `VOLUME-CHAPTER`
+// with the volume of the plan, the pages of chapter directories get their zero-padded position.
+// Chapters of nested layouts keep their names, as their volumes are given by the volume directories.
+pub fn propose(series: &Series, plan: &Plan, options: &RenameOptions) -> Result<RenamePlan, Error> {
+    let chapters: Vec<PathBuf> = plan
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(i, pages)| {
+            pages
+                .first()
+                .and_then(|page| archive::chapter(page))
+                .ok_or_else(|| Error::InvalidPlan(format!("Chapter {} has no pages", i + 1)))
+        })
+        .collect::<Result<_, _>>()?;
+    let mut renames: Vec<Rename> = Vec::new();
+
+    let mut push = |from: &PathBuf, name: String| {
+        let to = from.with_file_name(name);
+        if &to != from {
+            renames.push(Rename {
+                from: from.clone(),
+                to,
+            });
+        }
+    };
+
+    // Pages are renamed first, while their chapter directories still have the old names
+    if !options.skip_pages {
+        for (chapter, pages) in chapters.iter().zip(&plan.pages) {
+            // Pages inside of archives can't be renamed in place
+            if archive::is_archive(chapter) {
+                continue;
+            }
+
+            let width = pages.len().to_string().len().max(3);
+            for (i, page) in pages.iter().enumerate() {
+                push(page, with_extension(format!("{:0width$}", i + 1, width = width), page));
+            }
+        }
+    }
+
+    let nested = chapters
+        .iter()
+        .any(|chapter| chapter.parent() != Some(series.source.as_path()));
+
+    if !options.skip_chapters && !nested {
+        if plan.volume_sizes.iter().sum::<usize>() != chapters.len() {
+            return Err(Error::InvalidPlan(format!(
+                "The volumes contain {} chapters, but the plan has {}",
+                plan.volume_sizes.iter().sum::<usize>(),
+                chapters.len()
+            )));
+        }
+
+        // The chapter numbers and titles are kept where the naming convention or the natural order finds them
+        let parser = NameParser::new(&series.name_template).ok();
+        let volumes = plan
+            .volume_sizes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, size)| std::iter::repeat(i + 1).take(*size));
+
+        for (i, (chapter, volume)) in chapters.iter().zip(volumes).enumerate() {
+            let parsed = parser.as_ref().and_then(|parser| parser.parse(chapter));
+            let name = ChapterName {
+                volume: Some(volume),
+                chapter: parsed
+                    .as_ref()
+                    .and_then(|name| name.chapter)
+                    .or_else(|| Collector::chapter_number(chapter))
+                    .or(Some((i + 1) as f64)),
+                title: parsed.and_then(|name| name.title),
+            };
+
+            if let Some(name) = name.dashed() {
+                push(
+                    chapter,
+                    match archive::is_archive(chapter) {
+                        true => with_extension(name, chapter),
+                        false => name,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut targets: HashSet<&PathBuf> = HashSet::new();
+    if let Some(rename) = renames.iter().find(|rename| !targets.insert(&rename.to)) {
+        return Err(Error::InvalidPlan(format!(
+            "'{}' would be used for more than one chapter or page",
+            rename.to.display()
+        )));
+    }
+
+    Ok(RenamePlan {
+        source: series.source.clone(),
+        renames,
+    })
+}
+
+fn with_extension(name: String, path: &Path) -> String {
+    match path.extension() {
+        Some(extension) => format!("{}.{}", name, extension.to_string_lossy()),
+        None => name,
+    }
+}
+
+impl RenamePlan {
+    // One line per rename, relative to the source
+    pub fn diff(&self) -> String {
+        let relative = |path: &PathBuf| {
+            path.strip_prefix(&self.source)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        self.renames
+            .iter()
+            .map(|rename| format!("{} -> {}\n", relative(&rename.from), relative(&rename.to)))
+            .collect()
+    }
+
+    // Ranges of consecutive renames inside of the same directory
+    fn groups(&self) -> Vec<std::ops::Range<usize>> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+
+        while start < self.renames.len() {
+            let parent = self.renames[start].from.parent();
+            let end = self.renames[start..]
+                .iter()
+                .position(|rename| rename.from.parent() != parent)
+                .map_or(self.renames.len(), |length| start + length);
+
+            groups.push(start..end);
+            start = end;
+        }
+
+        groups
+    }
+
+    // The name of a path after all renames, e.g. to update the pages of a plan
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        self.groups().into_iter().fold(path.to_path_buf(), |path, group| {
+            self.renames[group]
+                .iter()
+                .find_map(|rename| match path.strip_prefix(&rename.from) {
+                    Ok(rest) if rest.as_os_str().is_empty() => Some(rename.to.clone()),
+                    Ok(rest) => Some(rename.to.join(rest)),
+                    Err(_) => None,
+                })
+                .unwrap_or(path)
+        })
+    }
+
+    // Updates the pages and covers of a plan to the new names
+    pub fn update(&self, plan: &mut Plan) {
+        for page in plan.pages.iter_mut().flatten() {
+            *page = self.resolve(page);
+        }
+        for cover in plan.covers.iter_mut() {
+            cover.cover = self.resolve(&cover.cover);
+        }
+    }
+
+    // Applies every rename and adds them to the journal of the source, so they can be undone.
+    // If a single rename fails, the ones before are reverted.
+    pub fn apply(&self) -> Result<usize, Error> {
+        let done = self.execute()?;
+
+        let mut journal = read_journal(&self.source)?;
+        journal.entries.push(JournalEntry {
+            applied: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            renames: self
+                .renames
+                .iter()
+                .map(|rename| Rename {
+                    from: rename.from.strip_prefix(&self.source).unwrap_or(&rename.from).to_path_buf(),
+                    to: rename.to.strip_prefix(&self.source).unwrap_or(&rename.to).to_path_buf(),
+                })
+                .collect(),
+        });
+
+        if let Err(e) = write_journal(&self.source, &journal) {
+            rollback(done);
+            return Err(e);
+        }
+
+        Ok(self.renames.len())
+    }
+
+    fn execute(&self) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+        for rename in &self.renames {
+            check_inside(&rename.from, &self.source)?;
+            check_inside(&rename.to, &self.source)?;
+        }
+
+        let mut done: Vec<(PathBuf, PathBuf)> = Vec::with_capacity(self.renames.len() * 2);
+
+        for group in self.groups() {
+            if let Err(e) = execute_group(&self.renames[group], &mut done) {
+                rollback(done);
+                return Err(e);
+            }
+        }
+
+        Ok(done)
+    }
+}
+
+// The renames of a group go through temporary names, so names can be swapped
+fn execute_group(group: &[Rename], done: &mut Vec<(PathBuf, PathBuf)>) -> Result<(), Error> {
+    for rename in group {
+        if !rename.from.exists() {
+            return Err(Error::NotFound(rename.from.display().to_string()).with_path(&rename.from));
+        }
+        if rename.to.exists() && !group.iter().any(|other| other.from == rename.to) {
+            return Err(Error::InvalidPath(rename.to.clone(), "The target already exists".to_string()));
+        }
+    }
+
+    let temporary: Vec<PathBuf> = group
+        .iter()
+        .enumerate()
+        .map(|(i, rename)| rename.from.with_file_name(format!(".palaxy-rename-{}", i)))
+        .collect();
+
+    let steps = group
+        .iter()
+        .zip(&temporary)
+        .map(|(rename, temporary)| (rename.from.clone(), temporary.clone()))
+        .chain(
+            group
+                .iter()
+                .zip(&temporary)
+                .map(|(rename, temporary)| (temporary.clone(), rename.to.clone())),
+        );
+
+    for (from, to) in steps {
+        fs::rename(&from, &to).map_err(|e| Error::from(e).with_path(&from))?;
+        done.push((from, to));
+    }
+
+    Ok(())
+}
+
+// Reverts executed renames, as far as possible
+fn rollback(done: Vec<(PathBuf, PathBuf)>) {
+    for (from, to) in done.into_iter().rev() {
+        let _ = fs::rename(to, from);
+    }
+}
+
+fn read_journal(source: &Path) -> Result<Journal, Error> {
+    let path = source.join(JOURNAL_NAME);
+
+    match path.exists() {
+        true => Ok(serde_json::from_str(&fs::read_to_string(&path)?)?),
+        false => Ok(Journal::default()),
+    }
+}
+
+// An empty journal is removed, so nothing is left behind in the source
+fn write_journal(source: &Path, journal: &Journal) -> Result<(), Error> {
+    let path = source.join(JOURNAL_NAME);
+
+    match journal.entries.is_empty() {
+        true if path.exists() => Ok(fs::remove_file(&path)?),
+        true => Ok(()),
+        false => Ok(fs::write(&path, serde_json::to_string_pretty(journal)?)?),
+    }
+}
+
+// Reverts the last applied renames of the source and returns the renames that were executed to do so
+pub fn undo(source: &Path) -> Result<RenamePlan, Error> {
+    let mut journal = read_journal(source)?;
+    let entry = journal
+        .entries
+        .pop()
+        .ok_or_else(|| Error::NotFound(format!("Renames to undo in {}", source.display())))?;
+
+    let plan = RenamePlan {
+        source: source.to_path_buf(),
+        renames: entry
+            .renames
+            .iter()
+            .rev()
+            .map(|rename| Rename {
+                from: source.join(&rename.to),
+                to: source.join(&rename.from),
+            })
+            .collect(),
+    };
+
+    let done = plan.execute()?;
+    if let Err(e) = write_journal(source, &journal) {
+        rollback(done);
+        return Err(e);
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str) -> PathBuf {
+        let source = std::env::temp_dir().join(format!("palaxy-rename-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&source);
+        fs::create_dir_all(&source).unwrap();
+        source
+    }
+
+    #[test]
+    fn apply_swaps_names_and_undo_restores_them() {
+        let source = source("swap");
+        fs::write(source.join("a.png"), "a").unwrap();
+        fs::write(source.join("b.png"), "b").unwrap();
+
+        let plan = RenamePlan {
+            source: source.clone(),
+            renames: vec![
+                Rename {
+                    from: source.join("a.png"),
+                    to: source.join("b.png"),
+                },
+                Rename {
+                    from: source.join("b.png"),
+                    to: source.join("a.png"),
+                },
+            ],
+        };
+
+        assert_eq!(plan.apply().unwrap(), 2);
+        assert_eq!(fs::read_to_string(source.join("a.png")).unwrap(), "b");
+        assert!(source.join(JOURNAL_NAME).exists());
+
+        assert_eq!(undo(&source).unwrap().renames.len(), 2);
+        assert_eq!(fs::read_to_string(source.join("a.png")).unwrap(), "a");
+        assert!(!source.join(JOURNAL_NAME).exists());
+        assert!(undo(&source).is_err());
+
+        fs::remove_dir_all(&source).unwrap();
+    }
+
+    #[test]
+    fn apply_reverts_all_renames_if_one_fails() {
+        let source = source("revert");
+        fs::write(source.join("a.png"), "a").unwrap();
+        fs::write(source.join("c.png"), "c").unwrap();
+        fs::create_dir_all(source.join("chapter")).unwrap();
+
+        let plan = RenamePlan {
+            source: source.clone(),
+            renames: vec![
+                Rename {
+                    from: source.join("a.png"),
+                    to: source.join("b.png"),
+                },
+                Rename {
+                    from: source.join("chapter").join("missing.png"),
+                    to: source.join("chapter").join("1.png"),
+                },
+            ],
+        };
+        let outside = RenamePlan {
+            source: source.clone(),
+            renames: vec![Rename {
+                from: source.join("c.png"),
+                to: source.join("..").join("c.png"),
+            }],
+        };
+
+        assert!(plan.apply().is_err());
+        assert!(source.join("a.png").exists());
+        assert!(!source.join("b.png").exists());
+        assert!(outside.apply().is_err());
+        assert!(source.join("c.png").exists());
+        assert!(!source.join(JOURNAL_NAME).exists());
+
+        fs::remove_dir_all(&source).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::archive;
+use crate::prelude::*;
+
+// The resources a conversion may use. Unset limits fall back to the defaults of the scheduler.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct ConvertLimits {
+    // Volumes that are converted at the same time
+    pub volumes: Option<usize>,
+    // Pages that are loaded but not yet written, shared by all volumes
+    pub pages: Option<usize>,
+    // Memory ceiling in megabytes for those pages and the volumes that are kept in memory
+    pub memory: Option<u64>,
+}
+
+fn threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+// Hands out the volume slots and the shared page budget of a conversion
+pub struct Scheduler {
+    volumes: Arc<Semaphore>,
+    budget: Arc<PageBudget>,
+}
+
+impl Scheduler {
+    pub fn new(limits: ConvertLimits) -> Self {
+        // The pages of a volume are already processed in parallel, so only a few volumes run at once
+        let volumes = limits.volumes.unwrap_or(2).max(1);
+        let pages = limits.pages.unwrap_or(threads() * 4).max(1);
+        let memory = limits.memory.map(|megabytes| megabytes.saturating_mul(1024 * 1024));
+
+        Self {
+            volumes: Arc::new(Semaphore::new(volumes)),
+            budget: Arc::new(PageBudget::new(pages, memory)),
+        }
+    }
+
+    // Waits until another volume may be converted. Its slot is freed once the permit is dropped.
+    pub async fn volume(&self) -> Result<OwnedSemaphorePermit, Error> {
+        self.volumes
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::AsyncTaskError(e.to_string()))
+    }
+
+    pub fn budget(&self) -> Arc<PageBudget> {
+        self.budget.clone()
+    }
+}
+
+// The memory a page takes up once it is loaded
+#[derive(Clone, Copy)]
+pub enum Footprint {
+    // The file itself, for pages that are written as they are
+    FILE,
+    // The decoded pixels, for pages that are embedded as images
+    DECODED,
+}
+
+// Bounds the number and size of the pages that are held in memory at the same time.
+// Volumes that keep their pages in memory until they are saved reserve that memory as well.
+pub struct PageBudget {
+    pages: usize,
+    memory: Option<u64>,
+    usage: Mutex<Usage>,
+    freed: Condvar,
+}
+
+#[derive(Default)]
+struct Usage {
+    batches: usize,
+    pages: usize,
+    reservations: usize,
+    bytes: u64,
+}
+
+impl Default for PageBudget {
+    fn default() -> Self {
+        Self::new(threads() * 4, None)
+    }
+}
+
+impl PageBudget {
+    pub fn new(pages: usize, memory: Option<u64>) -> Self {
+        Self {
+            pages: pages.max(1),
+            memory,
+            usage: Mutex::new(Usage::default()),
+            freed: Condvar::new(),
+        }
+    }
+
+    // Estimates the memory the pages take up once loaded.
+    // Without a memory ceiling, the size does not matter and the pages are not inspected.
+    fn sizes(&self, pages: &[PathBuf], footprint: Footprint) -> Result<Vec<u64>, Error> {
+        match (self.memory, footprint) {
+            (None, _) => Ok(vec![0; pages.len()]),
+            (Some(_), Footprint::FILE) => archive::sizes(pages),
+            (Some(_), Footprint::DECODED) => archive::decoded_sizes(pages),
+        }
+    }
+
+    // Loads the pages in parallel batches that fit into the budget and hands them over in their order.
+    pub fn load<T, L, C>(
+        &self,
+        pages: &[PathBuf],
+        footprint: Footprint,
+        cancellation: &CancellationToken,
+        load: L,
+        mut consume: C,
+    ) -> Result<(), Error>
+    where
+        T: Send,
+        L: Fn(&PathBuf) -> Result<T, Error> + Sync,
+        C: FnMut(&PathBuf, T) -> Result<(), Error>,
+    {
+        let sizes = self.sizes(pages, footprint)?;
+
+        let mut start = 0;
+        while start < pages.len() {
+            cancellation.check()?;
+
+            // A batch always contains at least one page, even if it exceeds the memory ceiling on its own
+            let mut end = start + 1;
+            let mut bytes = sizes[start];
+            while end < pages.len()
+                && end - start < self.pages
+                && self.memory.map_or(true, |memory| bytes + sizes[end] <= memory)
+            {
+                bytes += sizes[end];
+                end += 1;
+            }
+
+            let _batch = self.acquire(end - start, bytes);
+            let loaded: Vec<Result<T, Error>> = pages[start..end].par_iter().map(&load).collect();
+
+            for (page, item) in pages[start..end].iter().zip(loaded) {
+                cancellation.check()?;
+                item.and_then(|item| consume(page, item))
+                    .map_err(|e| e.with_path(page))?;
+            }
+
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Usage> {
+        self.usage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn exceeds(&self, usage: &Usage, bytes: u64) -> bool {
+        self.memory.map_or(false, |memory| usage.bytes + bytes > memory)
+    }
+
+    // Blocks until the memory retained by the pages of a volume fits into the budget.
+    // Oversized volumes only start while no other volume holds a reservation.
+    pub fn reserve<'a>(
+        &self,
+        pages: impl IntoIterator<Item = &'a PathBuf>,
+        footprint: Footprint,
+    ) -> Result<Reservation<'_>, Error> {
+        let pages: Vec<PathBuf> = pages.into_iter().cloned().collect();
+        let bytes = self.sizes(&pages, footprint)?.into_iter().sum();
+        let mut usage = self.lock();
+
+        while usage.reservations > 0 && self.exceeds(&usage, bytes) {
+            usage = self.freed.wait(usage).unwrap_or_else(PoisonError::into_inner);
+        }
+
+        usage.reservations += 1;
+        usage.bytes += bytes;
+
+        Ok(Reservation {
+            budget: self,
+            bytes,
+        })
+    }
+
+    // Blocks until the batch fits into the budget. Oversized batches only run while no other batch is loaded,
+    // so that volumes holding a reservation can always finish.
+    fn acquire(&self, pages: usize, bytes: u64) -> Batch<'_> {
+        let mut usage = self.lock();
+
+        while usage.batches > 0 && (usage.pages + pages > self.pages || self.exceeds(&usage, bytes)) {
+            usage = self.freed.wait(usage).unwrap_or_else(PoisonError::into_inner);
+        }
+
+        usage.batches += 1;
+        usage.pages += pages;
+        usage.bytes += bytes;
+
+        Batch {
+            budget: self,
+            pages,
+            bytes,
+        }
+    }
+}
+
+pub struct Reservation<'a> {
+    budget: &'a PageBudget,
+    bytes: u64,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut usage = self.budget.lock();
+        usage.reservations -= 1;
+        usage.bytes -= self.bytes;
+        self.budget.freed.notify_all();
+    }
+}
+
+struct Batch<'a> {
+    budget: &'a PageBudget,
+    pages: usize,
+    bytes: u64,
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        let mut usage = self.budget.lock();
+        usage.batches -= 1;
+        usage.pages -= self.pages;
+        usage.bytes -= self.bytes;
+        self.budget.freed.notify_all();
+    }
+}
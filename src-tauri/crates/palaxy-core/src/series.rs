@@ -0,0 +1,362 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::collector::Collector;
+use crate::diagnostics::{check_chapters, check_pages, Diagnostic, DiagnosticCode, Fix, Severity};
+use crate::naming::{NameParser, NameTemplate};
+use crate::prelude::*;
+use crate::rename::Rename;
+
+// A series on disk, either as a directory of chapters (directories or archives)
+// or as a directory of volume directories that contain the chapters
+#[derive(Clone)]
+pub struct Series {
+    pub source: PathBuf,
+    // Used for the names of the converted volumes
+    pub name: String,
+    pub name_template: NameTemplate,
+    progress: Option<ProgressReporter>,
+    cancellation: CancellationToken,
+}
+
+impl Series {
+    pub fn new(source: &Path) -> Self {
+        let name = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "Palaxy-Converted".to_string());
+
+        Self {
+            source: source.to_path_buf(),
+            name,
+            name_template: NameTemplate::default(),
+            progress: None,
+            cancellation: CancellationToken::default(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_name_template(mut self, name_template: NameTemplate) -> Self {
+        self.name_template = name_template;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn progress(&self) -> Option<ProgressReporter> {
+        self.progress.clone()
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    // Creates a collector for the source, which reports to the progress and cancellation of the series
+    pub fn collector(&self) -> Collector {
+        let collector = Collector::new(&self.source).with_cancellation(self.cancellation.clone());
+
+        match &self.progress {
+            Some(progress) => collector.with_progress(progress.clone()),
+            None => collector,
+        }
+    }
+}
+
+// The findings of `analyze` and the bundle flag that fits the series best
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub flag: BundleFlag,
+    // The detected naming convention, which is only used for bundling if the flag is `BundleFlag::NAME`
+    pub template: Option<NameTemplate>,
+}
+
+impl Analysis {
+    // Whether a diagnostic prevents the series from being bundled
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::ERROR)
+    }
+
+    // Adds a diagnostic for all given paths, or nothing if there are none
+    fn report(&mut self, diagnostic: Diagnostic, paths: Vec<PathBuf>) {
+        if !paths.is_empty() {
+            self.diagnostics.push(diagnostic.with_paths(paths));
+        }
+    }
+}
+
+// Renames the chapters to the `VOLUME-CHAPTER` convention, keeping the extension of archives.
+// Returns nothing if a chapter has no volume or two chapters would get the same name.
+fn dashed_renames(chapters: &[PathBuf], template: &NameTemplate) -> Option<Fix> {
+    let parser = NameParser::new(template).ok()?;
+    let mut renames: Vec<Rename> = Vec::with_capacity(chapters.len());
+
+    for chapter in chapters {
+        let mut name = parser.parse(chapter)?.dashed()?;
+
+        if archive::is_archive(chapter) {
+            if let Some(extension) = chapter.extension() {
+                name = format!("{}.{}", name, extension.to_string_lossy());
+            }
+        }
+
+        let to = chapter.with_file_name(name);
+        if renames.iter().any(|rename| rename.to == to) {
+            return None;
+        }
+        if &to != chapter {
+            renames.push(Rename {
+                from: chapter.clone(),
+                to,
+            });
+        }
+    }
+
+    Some(Fix::RENAME(renames))
+}
+
+pub async fn analyze(series: &Series) -> Result<Analysis, Error> {
+    fn has_perms(path: &PathBuf) -> bool {
+        path.metadata()
+            .map(|meta| !meta.permissions().readonly())
+            .unwrap_or(false)
+    }
+
+    let mut analysis = Analysis {
+        diagnostics: Vec::new(),
+        flag: BundleFlag::IMAGE,
+        template: None,
+    };
+
+    // Loose files next to the chapters make every collection fail, so they are looked for first
+    let stray_files: Vec<PathBuf> = std::fs::read_dir(&series.source)
+        .map_err(|e| Error::from(e).with_path(&series.source))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| !path.is_dir() && !archive::is_archive(path))
+        .collect();
+
+    if !stray_files.is_empty() {
+        analysis.report(
+            Diagnostic::new(
+                DiagnosticCode::StrayFiles,
+                Severity::ERROR,
+                "The base path contains files that are neither chapter directories nor archives. Remove them to continue.",
+            )
+            .with_fix(Some(Fix::DELETE(stray_files.clone()))),
+            stray_files,
+        );
+
+        return Ok(analysis);
+    }
+
+    let mut collector = series.collector();
+
+    // A nested layout is only assumed if every volume directory contains at least one chapter.
+    // Errors just rule out a nested layout, unless the analysis was cancelled.
+    let nested = match collector.collect_volumes(None).await {
+        Ok(volumes) => Some(volumes),
+        Err(Error::Cancelled) => return Err(Error::Cancelled),
+        Err(_) => None,
+    }
+    .filter(|volumes| !volumes.is_empty() && volumes.iter().all(|chapters| !chapters.is_empty()));
+
+    let chapters = match &nested {
+        Some(volumes) => volumes.concat(),
+        None => collector.collect_chapters(None).await?,
+    };
+    let chapter_pages = match chapters.is_empty() {
+        true => Vec::new(),
+        false => collector.collect_pages(chapters.clone(), None).await?,
+    };
+    let mut pages = chapter_pages.concat();
+
+    pages.retain(|path| archive::is_file(path));
+
+    if chapters.is_empty() {
+        analysis.report(
+            Diagnostic::new(
+                DiagnosticCode::NoChapters,
+                Severity::ERROR,
+                "No subdirectories or archives found in the base path. Ensure they exist with images inside.",
+            ),
+            vec![series.source.clone()],
+        );
+
+        return Ok(analysis);
+    }
+
+    if pages.is_empty() {
+        analysis.report(
+            Diagnostic::new(
+                DiagnosticCode::EmptyChapters,
+                Severity::ERROR,
+                "Subdirectories contain no files. Verify that chapter images are placed inside.",
+            ),
+            chapters,
+        );
+
+        return Ok(analysis);
+    }
+
+    // Pages inside an archive share the permissions of the archive
+    let mut read_only: Vec<PathBuf> = chapters.iter().filter(|chapter| !has_perms(chapter)).cloned().collect();
+    for page in pages.iter().map(|page| archive::container(page)) {
+        if !read_only.contains(&page) && !has_perms(&page) {
+            read_only.push(page);
+        }
+    }
+    analysis.report(
+        Diagnostic::new(
+            DiagnosticCode::ReadOnly,
+            Severity::WARNING,
+            "Some chapters or pages lack write permissions, which are required for full functionality.",
+        ),
+        read_only,
+    );
+
+    // Archives only contain images, so stray pages can always be deleted
+    let (stray_pages, pages): (Vec<PathBuf>, Vec<PathBuf>) =
+        pages.into_iter().partition(|page| get_file_info(page).is_err());
+    analysis.report(
+        Diagnostic::new(
+            DiagnosticCode::StrayFiles,
+            Severity::ERROR,
+            "Some chapters contain files that are not supported images. Remove them to continue.",
+        )
+        .with_fix(Some(Fix::DELETE(stray_pages.clone()))),
+        stray_pages,
+    );
+
+    if nested.is_some() {
+        // The volumes of a nested layout are given by its directories, so the naming convention does not matter
+        analysis.report(
+            Diagnostic::new(
+                DiagnosticCode::NestedLayout,
+                Severity::INFO,
+                "Nested volume directories detected. Volumes will be bundled based on the directory structure.",
+            ),
+            vec![series.source.clone()],
+        );
+        analysis.flag = BundleFlag::FOLDER;
+    } else {
+        let dir_lacks_numeric = Collector::check_path(&chapters, |path| {
+            path.file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .chars()
+                .any(char::is_numeric)
+        })?;
+
+        analysis.template = NameTemplate::detect(&chapters, &series.name_template);
+
+        match &analysis.template {
+            None => analysis.report(
+                Diagnostic::new(
+                    DiagnosticCode::UnknownNaming,
+                    Severity::INFO,
+                    "Subdirectory naming convention not recognized; use e.g. 'VOLUME-CHAPTER' ('002-032'), 'Vol.02 Ch.032' or 'v02c032', or provide a custom template for faster bundling.",
+                ),
+                chapters.clone(),
+            ),
+            Some(NameTemplate::DASHED) => {}
+            Some(template) => {
+                let fix = dashed_renames(&chapters, template);
+                let paths = match &fix {
+                    Some(Fix::RENAME(renames)) => renames.iter().map(|rename| rename.from.clone()).collect(),
+                    _ => chapters.clone(),
+                };
+
+                analysis.report(
+                    Diagnostic::new(
+                        DiagnosticCode::NonDashedNaming,
+                        Severity::INFO,
+                        "Subdirectories follow a known naming convention, but can be renamed to 'VOLUME-CHAPTER' ('002-032').",
+                    )
+                    .with_fix(fix),
+                    paths,
+                )
+            }
+        }
+
+        analysis.report(
+            Diagnostic::new(
+                DiagnosticCode::DirLacksNumber,
+                Severity::WARNING,
+                "Some directories lack numerical identifiers. Remove them for faster bundling.",
+            ),
+            dir_lacks_numeric.clone(),
+        );
+
+        if dir_lacks_numeric.is_empty() && analysis.template.is_some() {
+            analysis.report(
+                Diagnostic::new(
+                    DiagnosticCode::NamingDetected,
+                    Severity::INFO,
+                    "Directories correctly named and numbered. Automatic bundling will proceed with the fastest algorithm.",
+                ),
+                vec![series.source.clone()],
+            );
+            analysis.flag = BundleFlag::NAME;
+        } else {
+            analysis.report(
+                Diagnostic::new(
+                    DiagnosticCode::FallbackBundling,
+                    Severity::INFO,
+                    "Automatic bundling will use fallback mechanisms, potentially slowing the process and increasing error risk.",
+                ),
+                vec![series.source.clone()],
+            );
+        }
+    }
+
+    analysis.diagnostics.extend(check_pages(&chapters, &chapter_pages));
+
+    let volumes = match nested {
+        Some(volumes) => volumes,
+        None => vec![chapters],
+    };
+    analysis
+        .diagnostics
+        .extend(check_chapters(&volumes, analysis.template.as_ref()));
+
+    let file_lack_numeric = Collector::check_path(&pages, |path| {
+        path.file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .replace(".", "")
+            .chars()
+            .all(char::is_numeric)
+    })?;
+
+    analysis.report(
+        Diagnostic::new(
+            DiagnosticCode::FileLacksNumber,
+            Severity::WARNING,
+            "Some files lack numerical naming, which is required for effective sorting and bundling.",
+        ),
+        file_lack_numeric,
+    );
+
+    Ok(analysis)
+}
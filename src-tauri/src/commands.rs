@@ -1,20 +1,14 @@
-use crate::collector::Collector;
-use crate::generator::{cbz, epub, pdf};
+use crate::jobs::{Job, JobId, JobProcess, JobProgress, JobRegistry, JobStatus};
 use crate::prelude::*;
-use lazy_static::lazy_static;
-use rayon::prelude::*;
-use regex::Regex;
-use std::path::{Path, PathBuf};
+use palaxy_core::naming::{NameParser, NameTemplate};
+use palaxy_core::scheduler::ConvertLimits;
+use palaxy_core::{rename, ConvertOptions, DuplicatePolicy, Fix, Library, PlanFile, PlanOptions, Project, RenameOptions, RenamePlan, Series};
+use std::future::Future;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::async_runtime::{spawn, spawn_blocking, JoinHandle};
-use tauri::State;
-use tokio::fs::create_dir;
+use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 
-lazy_static! {
-    static ref REGEX_ANALYZE: Regex = Regex::new(r"\d+-\d+(\.\d+)?").unwrap();
-}
-
 // -- RESET --
 
 #[tauri::command(async)]
@@ -24,18 +18,21 @@ pub async fn reset(state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault>
     Ok(CommandDefault::default())
 }
 
+#[tauri::command(async)]
+pub async fn cancel(cancellation: State<'_, CancellationToken>) -> EResult<CommandDefault> {
+    cancellation.cancel();
+    Ok(CommandDefault {
+        message: Some("Cancelling the running process.".to_string()),
+    })
+}
+
 // -- SETTER --
 
 #[tauri::command(async)]
 pub async fn set_source(source: String, state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault> {
     let mut state = state.lock().await;
     state.source = PathBuf::from(source);
-
-    state.name = state.source
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name.to_string())
-        .unwrap_or_else(|| "Palaxy-Converted".to_string());
+    state.name = Series::new(&state.source).name;
 
     Ok(CommandDefault::default())
 }
@@ -54,6 +51,16 @@ pub async fn set_bundle_flag(flag: BundleFlag, state: State<'_, Mutex<AppState>>
     Ok(CommandDefault::default())
 }
 
+#[tauri::command(async)]
+pub async fn set_name_template(template: NameTemplate, state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault> {
+    // Make sure custom templates are valid before they are used for bundling
+    NameParser::new(&template)?;
+
+    let mut state = state.lock().await;
+    state.name_template = template;
+    Ok(CommandDefault::default())
+}
+
 #[tauri::command(async)]
 pub async fn set_data(data: Vec<Vec<PathBuf>>, state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault> {
     let mut state = state.lock().await;
@@ -61,6 +68,67 @@ pub async fn set_data(data: Vec<Vec<PathBuf>>, state: State<'_, Mutex<AppState>>
     Ok(CommandDefault::default())
 }
 
+// Without a policy, chapters with the same number are all bundled
+#[tauri::command(async)]
+pub async fn set_duplicate_policy(
+    policy: Option<DuplicatePolicy>,
+    state: State<'_, Mutex<AppState>>,
+) -> EResult<CommandDefault> {
+    let mut state = state.lock().await;
+    state.duplicates = policy;
+    Ok(CommandDefault::default())
+}
+
+#[tauri::command(async)]
+pub async fn set_convert_limits(
+    limits: ConvertLimits,
+    state: State<'_, Mutex<AppState>>,
+    registry: State<'_, Mutex<JobRegistry>>,
+) -> EResult<CommandDefault> {
+    // The jobs share the limits, but are updated first as the app state is locked by running processes
+    let mut registry = registry.lock().await;
+    registry.limits = limits;
+    registry.save()?;
+    drop(registry);
+
+    let mut state = state.lock().await;
+    state.convert_limits = limits;
+    Ok(CommandDefault::default())
+}
+
+// Overrides the bundle options of a single series of the library, or skips it
+#[tauri::command(async)]
+pub async fn set_library_entry(
+    source: String,
+    name: Option<String>,
+    name_template: Option<NameTemplate>,
+    options: Option<PlanOptions>,
+    skip: bool,
+    state: State<'_, Mutex<AppState>>,
+) -> EResult<CommandDefault> {
+    let mut state = state.lock().await;
+    let source = PathBuf::from(source);
+    let entry = state
+        .library
+        .entries
+        .iter_mut()
+        .find(|entry| entry.source == source)
+        .ok_or_else(|| Error::NotFound(format!("Series {:?} is not part of the library", source)))?;
+
+    if let Some(name) = name {
+        entry.name = name;
+    }
+    if let Some(template) = name_template {
+        // Make sure custom templates are valid before they are used for bundling
+        NameParser::new(&template)?;
+        entry.name_template = template;
+    }
+    entry.options = options;
+    entry.skip = skip;
+
+    Ok(CommandDefault::default())
+}
+
 // -- GETTER --
 
 #[tauri::command(async)]
@@ -72,259 +140,159 @@ pub async fn get_data(state: State<'_, Mutex<AppState>>) -> EResult<CommandGetDa
     })
 }
 
-// -- PROCESSES --
-
 #[tauri::command(async)]
-pub async fn analyze(state: State<'_, Mutex<AppState>>) -> EResult<CommandAnalyze> {
+pub async fn get_library(state: State<'_, Mutex<AppState>>) -> EResult<CommandLibrary> {
     let state = state.lock().await;
+    Ok(CommandLibrary {
+        message: None,
+        root: state.library.root.clone(),
+        entries: state.library.entries.clone(),
+    })
+}
 
-    fn has_perms(path: &PathBuf) -> bool {
-        path.metadata()
-            .map(|meta| !meta.permissions().readonly())
-            .unwrap_or(false)
-    }
-
-    let mut negative = Vec::new();
-    let mut positive = Vec::new();
-    let mut suggest = Vec::new();
-    let mut flag = BundleFlag::IMAGE;
-    let mut collector = Collector::new(&state.source);
-
-    let chapters = collector.collect_chapters(None).await?;
-    let mut pages = match chapters.is_empty() {
-        true => Vec::new(),
-        false => collector
-            .collect_pages(chapters.clone(), None)
-            .await?
-            .concat(),
-    };
-
-    pages.retain(|path| path.is_file());
-
-    if chapters.is_empty() {
-        negative.push(
-            "No subdirectories found in the base path. Ensure they exist with images inside."
-                .to_string(),
-        );
-
-        return Ok(CommandAnalyze {
-            message: None,
-            negative,
-            positive,
-            suggest,
-            flag,
-        });
-    }
-
-    if pages.is_empty() && !chapters.is_empty() {
-        negative.push(
-            "Subdirectories contain no files. Verify that chapter images are placed inside."
-                .to_string(),
-        );
-
-        return Ok(CommandAnalyze {
-            message: None,
-            negative,
-            positive,
-            suggest,
-            flag,
-        });
-    }
+// -- PROCESSES --
 
-    let dir_lacks_numeric = Collector::check_path(&chapters, |path| {
-        path.file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .chars()
-            .any(char::is_numeric)
-    })?;
-
-    dir_lacks_numeric.iter().for_each(|dir| {
-        negative.push(format!(
-            "Directory {:?} lacks numerical identifiers. Remove them for faster bundling.",
-            dir.file_name().unwrap()
-        ));
-    });
-
-    chapters.iter().for_each(|chapter| {
-        if !has_perms(chapter) {
-            negative.push(format!(
-                "Directory {:?} lacks write permissions. Required for full functionality.",
-                chapter.file_name().unwrap()
-            ));
-        }
-    });
-
-    pages.iter().for_each(|page| {
-        if !has_perms(page) {
-            negative.push(format!(
-                "File '{:?}' lacks write permissions. Required for full functionality.",
-                page.file_name().unwrap()
-            ));
-        }
-    });
+// Emits the progress of a process as `progress` event to the frontend
+fn progress_reporter(app: AppHandle) -> ProgressReporter {
+    Arc::new(move |progress| {
+        // A lost event only affects the progress display, not the process itself
+        let _ = app.emit("progress", progress);
+    })
+}
 
-    let dir_lacks_naming = Collector::check_path(&chapters, |path| {
-        REGEX_ANALYZE.is_match(path.file_name().unwrap().to_str().unwrap())
-    })?;
+#[tauri::command(async)]
+pub async fn analyze(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandAnalyze> {
+    let mut state = state.lock().await;
+    cancellation.reset();
 
-    if !dir_lacks_naming.is_empty() {
-        suggest.push("Subdirectory naming convention not followed; use 'VOLUME-CHAPTER' (e.g., '002-032') for faster bundling.".to_string());
-    }
+    let series = state
+        .series()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    let analysis = palaxy_core::analyze(&series).await?;
 
-    if dir_lacks_numeric.is_empty() && dir_lacks_naming.is_empty() {
-        positive.push("Directories correctly named and numbered. Automatic bundling will proceed with the fastest algorithm.".to_string());
-        flag = BundleFlag::NAME;
-    } else {
-        positive.push("Automatic bundling will use fallback mechanisms, potentially slowing the process and increasing error risk.".to_string());
+    // The detected naming convention is used for bundling by name
+    if let (BundleFlag::NAME, Some(template)) = (&analysis.flag, &analysis.template) {
+        state.name_template = template.clone();
     }
 
-    let file_lack_numeric = Collector::check_path(&pages, |path| {
-        path.file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .replace(".", "")
-            .chars()
-            .all(char::is_numeric)
-    })?;
-
-    file_lack_numeric.iter().for_each(|file| {
-        negative.push(format!(
-            "File {:?} lacks numerical naming. Required for effective sorting and bundling.",
-            file.file_name().unwrap()
-        ));
-    });
-
     Ok(CommandAnalyze {
         message: None,
-        negative,
-        positive,
-        suggest,
-        flag,
+        diagnostics: analysis.diagnostics,
+        flag: analysis.flag,
+        template: analysis.template,
+    })
+}
+
+// Executes the fix of a diagnostic, which may only change paths inside of the source.
+// The collected pages are dropped, as they may point to renamed or deleted files.
+#[tauri::command(async)]
+pub async fn apply_fix(fix: Fix, state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault> {
+    let mut state = state.lock().await;
+    let changed = palaxy_core::diagnostics::apply(&fix, &state.source)?;
+
+    state.data = Vec::default();
+    state.volume_sizes = Vec::default();
+
+    Ok(CommandDefault {
+        message: Some(format!("Changed {} paths, analyze the source again.", changed)),
     })
 }
 
 #[tauri::command(async)]
 pub async fn bundle(
     sensibility: Option<usize>,
+    page_target: Option<usize>,
+    page_tolerance: Option<usize>,
+    detectors: Option<Vec<DetectorKind>>,
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
 ) -> EResult<CommandBundle> {
     let now = std::time::Instant::now();
     let mut state = state.lock().await;
-    let mut collector = Collector::new(&state.source);
-
-    // Collect all pages and sort based on bundle_flag
-    let mut chapters: Vec<PathBuf> = collector
-        .collect_chapters(if state.bundle_flag == BundleFlag::IMAGE {
-            Some(&Collector::sort_name_by_number)
-        } else {
-            None
-        })
-        .await?;
-
-    let pages: Vec<Vec<PathBuf>> = collector
-        .collect_pages(chapters.clone(), Some(&Collector::sort_by_stem_number))
-        .await?;
-
-    let total_chapters: usize = chapters.len();
-    let mut total_volumes: usize = 0;
-    let mut chapter_sizes: Vec<usize> = Vec::default();
-
-    match state.bundle_flag {
-        // For manual bundling, the user will have to manually input the remaining information.
-        // This will be done in the frontend.
-        BundleFlag::MANUAL => {}
-        // For automatic bundling by name,
-        // the program will use the naming convention
-        // to determine the volumes and chapters.
-        BundleFlag::NAME => {
-            let mut tmp = Vec::new();
-            let mut extra = false;
-
-            // Sort the chapters by their chapter number
-            chapters.par_sort_by(Collector::sort_by_name_volume_chapter);
-
-            // Determine the start of each volume
-            for (i, chapter) in chapters.iter().enumerate() {
-                let volume_number = chapter
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .and_then(|s| s.split('-').next())
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(0);
-
-                if volume_number == 0 {
-                    extra = true;
-                }
-
-                if tmp.len() < volume_number {
-                    tmp.push(i);
-                }
-            }
+    cancellation.reset();
+
+    let series = state
+        .series()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    let plan = palaxy_core::plan(
+        &series,
+        &PlanOptions {
+            flag: state.bundle_flag.clone(),
+            sensibility,
+            page_target,
+            page_tolerance,
+            detectors,
+            // Manual sizes are set by the frontend after bundling
+            sizes: None,
+            duplicates: state.duplicates.clone(),
+        },
+    )
+    .await?;
 
-            if extra {
-                tmp.push(0);
-            }
-
-            // Calculate the number of chapters per volume based on the start chapters of each volume
-            let mut tmp2: Vec<usize> = Vec::new();
-            for i in 0..tmp.len() {
-                if i == tmp.len() - 1 {
-                    tmp2.push(chapters.len() - tmp[i]);
-                } else {
-                    tmp2.push(tmp[i + 1] - tmp[i]);
-                }
-            }
-
-            chapter_sizes = tmp2;
-            total_volumes = tmp.len();
-        }
-        // The image version uses the grayscale detection algorithm to determine the start of each volume.
-        // This is done by checking the first image of each chapter.
-        BundleFlag::IMAGE => {
-            let volume_start_chapters = collector
-                .determine_volume_start_chapters(
-                    pages.clone(),
-                    sensibility.map_or(0.75, |s| s as f64 / 100.0),
-                )
-                .await?;
-
-            total_volumes = volume_start_chapters.len();
-            chapter_sizes =
-                collector.calculate_volume_sizes(volume_start_chapters, total_chapters)?;
-        }
-    };
+    let total_chapters = plan.total_chapters();
+    let total_volumes = plan.total_volumes();
 
     // Set the new states
-    state.volume_sizes = chapter_sizes.clone();
-    state.data = pages;
+    state.volume_sizes = plan.volume_sizes.clone();
+    state.data = plan.pages;
+
+    let rejected: usize = plan.duplicates.iter().map(|choice| choice.rejected.len()).sum();
 
     Ok(CommandBundle {
-        message: Some(format!(
-            "Bundling completed in {:.2?} seconds.",
-            now.elapsed().as_secs_f64()
-        )),
+        message: Some(match rejected {
+            0 => format!("Bundling completed in {:.2?} seconds.", now.elapsed().as_secs_f64()),
+            _ => format!(
+                "Bundling completed in {:.2?} seconds, {} duplicate chapters were left out.",
+                now.elapsed().as_secs_f64(),
+                rejected
+            ),
+        }),
         total_chapters,
         total_volumes: if total_volumes > 0 {
             Some(total_volumes)
         } else {
             None
         },
-        chapter_sizes: if !chapter_sizes.is_empty() {
-            Some(chapter_sizes)
+        chapter_sizes: if !plan.volume_sizes.is_empty() {
+            Some(plan.volume_sizes)
+        } else {
+            None
+        },
+        covers: if !plan.covers.is_empty() {
+            Some(plan.covers)
+        } else {
+            None
+        },
+        duplicates: if !plan.duplicates.is_empty() {
+            Some(plan.duplicates)
         } else {
             None
         },
     })
 }
 
-struct SharedData {
-    name: String,
-    target_directory: String,
-    pages: Vec<Vec<PathBuf>>,
-    chapters_per_volume: Vec<usize>,
+// Checks the plan of the app state without converting it, so issues can be fixed before anything is written
+#[tauri::command(async)]
+pub async fn validate_plan(state: State<'_, Mutex<AppState>>) -> EResult<CommandValidatePlan> {
+    let state = state.lock().await;
+    let plan = state.plan();
+    let issues = plan.validate();
+
+    Ok(CommandValidatePlan {
+        message: match issues.len() {
+            0 => None,
+            count => Some(format!("The plan has {} issues.", count)),
+        },
+        valid: issues.is_empty(),
+        issues,
+    })
 }
 
 #[tauri::command(async)]
@@ -333,135 +301,687 @@ pub async fn convert(
     target: String,
     file_format: FileFormat,
     direction: Direction,
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
-) -> EResult<CommandDefault> {
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandConvert> {
     let now = std::time::Instant::now();
     let state = state.lock().await;
+    cancellation.reset();
+
+    let series = state
+        .series()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    let plan = state.plan();
+    let conversion = palaxy_core::convert(
+        &series,
+        &plan,
+        &ConvertOptions {
+            target: PathBuf::from(target),
+            create_directory,
+            file_format,
+            direction,
+            limits: state.convert_limits,
+        },
+    )
+    .await?;
+    let elapsed = now.elapsed();
 
-    // Get all the state data needed
-    let target_directory_path = match create_directory {
-        true => {
-            let path = Path::new(&target).join(&state.name);
-            if !path.exists() {
-                create_dir(&path).await?;
-            }
-            Ok(path)
-        }
-        false => {
-            let path = Path::new(&target);
-            if !path.exists() {
-                Err(Error::Io(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Target directory does not exist",
-                )))
-            } else {
-                Ok(PathBuf::from(path))
-            }
-        }
-    }?
-        .to_str()
-        .unwrap()
-        .to_string();
-
-    let data = Arc::new(SharedData {
-        name: state.name.clone(),
-        target_directory: target_directory_path,
-        pages: state.data.clone(),
-        chapters_per_volume: state.volume_sizes.clone(),
-    });
-
-    let handles: Vec<JoinHandle<Result<(), Error>>> = state
-        .volume_sizes
-        .clone()
-        .into_iter()
-        .enumerate()
-        .map(|(i, chapters)| {
-            let data = Arc::clone(&data);
+    Ok(CommandConvert {
+        message: Some(match conversion.failed {
+            0 => format!(
+                "Conversion completed in {:.2?} seconds.",
+                elapsed.as_secs_f64()
+            ),
+            _ => format!(
+                "Conversion partially completed in {:.2?} seconds, {} of {} volumes failed.",
+                elapsed.as_secs_f64(),
+                conversion.failed,
+                conversion.volumes.len()
+            ),
+        }),
+        failed: conversion.failed,
+        volumes: conversion.volumes,
+    })
+}
 
-            // Spawn a new thread for each volume but make sure to use the correct spawning method
-            match file_format {
-                FileFormat::CBZ => spawn_blocking(move || {
-                    let j: usize = data.chapters_per_volume[0..i].par_iter().sum();
+// -- PLAN FILE --
 
-                    let volume_name = format!("{} | {}", data.name, i + 1);
+// Writes the volumes and their chapters as JSON or CSV (by the extension of the path)
+#[tauri::command(async)]
+pub async fn export_plan(path: String, state: State<'_, Mutex<AppState>>) -> EResult<CommandDefault> {
+    let state = state.lock().await;
+    let plan = state.plan();
+    let path = PathBuf::from(path);
+    PlanFile::from_plan(&plan, &state.source)?.write(&path)?;
 
-                    let mut cbz = cbz::Cbz::new(&data.target_directory, &volume_name)?;
+    Ok(CommandDefault {
+        message: Some(format!("Plan exported to {:?}.", path)),
+    })
+}
 
-                    for k in j..(j + chapters) {
-                        for page in &data.pages[k] {
-                            cbz.add_page(page)?;
-                        }
-                    }
+// Replaces the plan with an edited or generated one, whose chapters have to exist in the source
+#[tauri::command(async)]
+pub async fn import_plan(
+    path: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandBundle> {
+    let mut state = state.lock().await;
+    cancellation.reset();
 
-                    cbz.set_comicinfo(&volume_name, i + 1)?;
-                    cbz.save()?;
+    let series = state
+        .series()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    let import = PlanFile::read(&PathBuf::from(path))?.import(&series).await?;
 
-                    Ok(())
-                }),
-                FileFormat::EPUB => spawn(async move {
-                    let j: usize = data.chapters_per_volume[0..i].par_iter().sum();
+    let total_chapters = import.plan.total_chapters();
+    let total_volumes = import.plan.total_volumes();
+    state.volume_sizes = import.plan.volume_sizes.clone();
+    state.data = import.plan.pages;
 
-                    let volume_name = format!("{} | {}", data.name, i + 1);
+    Ok(CommandBundle {
+        message: match import.omitted.len() {
+            0 => Some("Plan imported.".to_string()),
+            omitted => Some(format!(
+                "Plan imported, {} chapters of the source are not part of it: {}.",
+                omitted,
+                import.omitted.join(", ")
+            )),
+        },
+        total_chapters,
+        total_volumes: Some(total_volumes),
+        chapter_sizes: Some(import.plan.volume_sizes),
+        covers: None,
+        duplicates: None,
+    })
+}
 
-                    let mut epub = epub::EPub::new()?;
+// -- RENAME --
 
-                    epub.set_cover(&data.pages[j][0])?
-                        .set_lang("en")?
-                        .set_metadata("title", &volume_name)?
-                        .set_metadata("author", "Manga Bundler")?
-                        .set_metadata(
-                            "direction",
-                            if direction == Direction::LTR {
-                                "ltr"
-                            } else {
-                                "rtl"
-                            },
-                        )?;
+// The names of the chapters and pages in the order of the current plan
+fn propose_renames(state: &AppState, skip_chapters: bool, skip_pages: bool) -> EResult<RenamePlan> {
+    let plan = state.plan();
 
-                    for k in j..(j + chapters) {
-                        epub.add_chapter(k + 1, &data.pages[k]).await?;
-                    }
+    rename::propose(
+        &state.series(),
+        &plan,
+        &RenameOptions {
+            skip_chapters,
+            skip_pages,
+        },
+    )
+}
 
-                    epub.save(&data.target_directory, format!("{}", volume_name).as_str())
-                        .await?;
+// The pages of the plan are kept up to date, so the source does not have to be bundled again
+fn rename_pages(state: &mut AppState, renames: &RenamePlan) {
+    for page in state.data.iter_mut().flatten() {
+        *page = renames.resolve(page);
+    }
+}
 
-                    Ok(())
-                }),
-                FileFormat::PDF => spawn_blocking(move || {
-                    let j: usize = data.chapters_per_volume[0..i].par_iter().sum();
+#[tauri::command(async)]
+pub async fn preview_renames(
+    skip_chapters: bool,
+    skip_pages: bool,
+    state: State<'_, Mutex<AppState>>,
+) -> EResult<CommandRenames> {
+    let state = state.lock().await;
+    let renames = propose_renames(&state, skip_chapters, skip_pages)?;
 
-                    let volume_name = format!("{} | {}", data.name, i + 1);
+    Ok(CommandRenames {
+        message: match renames.renames.len() {
+            0 => Some("Chapters and pages are already named correctly.".to_string()),
+            _ => None,
+        },
+        diff: renames.diff(),
+        renames,
+    })
+}
+
+#[tauri::command(async)]
+pub async fn apply_renames(
+    skip_chapters: bool,
+    skip_pages: bool,
+    state: State<'_, Mutex<AppState>>,
+) -> EResult<CommandRenames> {
+    let mut state = state.lock().await;
+    let renames = propose_renames(&state, skip_chapters, skip_pages)?;
+    let renamed = renames.apply()?;
+    rename_pages(&mut state, &renames);
+
+    Ok(CommandRenames {
+        message: Some(format!("Renamed {} chapters and pages.", renamed)),
+        diff: renames.diff(),
+        renames,
+    })
+}
 
-                    let mut pdf = pdf::Pdf::new(&volume_name, &data.pages[j][0])?;
+// Reverts the last applied renames of the source, including fixes of the analysis
+#[tauri::command(async)]
+pub async fn undo_renames(state: State<'_, Mutex<AppState>>) -> EResult<CommandRenames> {
+    let mut state = state.lock().await;
+    let renames = rename::undo(&state.source)?;
+    rename_pages(&mut state, &renames);
 
-                    for k in (j + 1)..(j + chapters) {
-                        for page in &data.pages[k] {
-                            pdf.add_page(page)?;
-                        }
-                    }
+    Ok(CommandRenames {
+        message: Some(format!("Reverted {} renames.", renames.renames.len())),
+        diff: renames.diff(),
+        renames,
+    })
+}
 
-                    pdf.save(&data.target_directory, &volume_name)?;
+// -- PROJECT --
 
-                    Ok(())
-                }),
-            }
+// Saves the source, plan and convert options, so manual changes to the plan survive a restart
+#[tauri::command(async)]
+pub async fn save_project(
+    path: String,
+    convert: Option<ConvertOptions>,
+    state: State<'_, Mutex<AppState>>,
+) -> EResult<CommandDefault> {
+    let state = state.lock().await;
+    let plan = state.plan();
+    let path = Project::new(&state.series(), state.bundle_flag.clone(), &plan, convert)
+        .with_duplicates(state.duplicates.clone())
+        .with_limits(state.convert_limits)
+        .save(&PathBuf::from(path))?;
+
+    Ok(CommandDefault {
+        message: Some(format!("Project saved to {:?}.", path)),
+    })
+}
+
+// Loads a project into the app state. If its source was moved, it is looked for next to the project file,
+// otherwise a new source has to be given. The duplicate policy and convert limits of the project are restored as well.
+#[tauri::command(async)]
+pub async fn load_project(
+    path: String,
+    source: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    registry: State<'_, Mutex<JobRegistry>>,
+) -> EResult<CommandProject> {
+    let path = PathBuf::from(path);
+    let mut project = Project::load(&path)?;
+    let project_source = project.relocate(&path, source.map(PathBuf::from).as_deref())?;
+
+    // The jobs share the limits, see `set_convert_limits`
+    if let Some(limits) = project.limits {
+        let mut registry = registry.lock().await;
+        registry.limits = limits;
+        registry.save()?;
+    }
+
+    let mut state = state.lock().await;
+    let plan = project.plan();
+    state.reset();
+    state.duplicates = project.duplicates.clone();
+    if let Some(limits) = project.limits {
+        state.convert_limits = limits;
+    }
+    state.source = project.source.clone();
+    state.name = project.name.clone();
+    state.name_template = project.name_template.clone();
+    state.bundle_flag = project.flag.clone();
+    state.volume_sizes = plan.volume_sizes;
+    state.data = plan.pages;
+
+    let mut messages = Vec::new();
+    if let Some(saved) = &project_source.relocated {
+        messages.push(format!(
+            "The source moved from {:?} to {:?}.",
+            saved, project_source.source
+        ));
+    }
+    if project_source.missing_pages > 0 {
+        messages.push(format!(
+            "{} pages of the plan do not exist anymore, bundle again before converting.",
+            project_source.missing_pages
+        ));
+    }
+
+    Ok(CommandProject {
+        message: match messages.is_empty() {
+            true => None,
+            false => Some(messages.join(" ")),
+        },
+        project,
+        source: project_source,
+    })
+}
+
+// -- LIBRARY --
+
+#[tauri::command(async)]
+pub async fn scan_library(
+    root: String,
+    state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandLibrary> {
+    let mut state = state.lock().await;
+    cancellation.reset();
+
+    let mut library = Library::new(&PathBuf::from(root)).with_cancellation(cancellation.inner().clone());
+    library.scan().await?;
+    state.library = library;
+
+    Ok(CommandLibrary {
+        message: Some(format!("Found {} series.", state.library.entries.len())),
+        root: state.library.root.clone(),
+        entries: state.library.entries.clone(),
+    })
+}
+
+#[tauri::command(async)]
+pub async fn bundle_library(
+    sensibility: Option<usize>,
+    page_target: Option<usize>,
+    page_tolerance: Option<usize>,
+    detectors: Option<Vec<DetectorKind>>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandLibrary> {
+    let now = std::time::Instant::now();
+    let mut state = state.lock().await;
+    cancellation.reset();
+
+    let mut library = state
+        .library
+        .clone()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    library
+        .prepare(&PlanOptions {
+            sensibility,
+            page_target,
+            page_tolerance,
+            detectors,
+            duplicates: state.duplicates.clone(),
+            ..PlanOptions::default()
         })
-        .collect();
+        .await?;
+    state.library.entries = library.entries;
+
+    let failed = state.library.entries.iter().filter(|entry| entry.error.is_some()).count();
+
+    Ok(CommandLibrary {
+        message: Some(match failed {
+            0 => format!("Bundling completed in {:.2?} seconds.", now.elapsed().as_secs_f64()),
+            _ => format!(
+                "Bundling completed in {:.2?} seconds, {} series could not be bundled.",
+                now.elapsed().as_secs_f64(),
+                failed
+            ),
+        }),
+        root: state.library.root.clone(),
+        entries: state.library.entries.clone(),
+    })
+}
+
+#[tauri::command(async)]
+pub async fn convert_library(
+    create_directory: bool,
+    target: String,
+    file_format: FileFormat,
+    direction: Direction,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    cancellation: State<'_, CancellationToken>,
+) -> EResult<CommandConvertLibrary> {
+    let now = std::time::Instant::now();
+    let state = state.lock().await;
+    cancellation.reset();
+
+    let library = state
+        .library
+        .clone()
+        .with_progress(progress_reporter(app))
+        .with_cancellation(cancellation.inner().clone());
+    let report = library
+        .convert(&ConvertOptions {
+            target: PathBuf::from(target),
+            create_directory,
+            file_format,
+            direction,
+            limits: state.convert_limits,
+        })
+        .await?;
+
+    Ok(CommandConvertLibrary {
+        message: Some(format!(
+            "Converted {} of {} series ({} volumes) in {:.2?} seconds, {} failed and {} skipped.",
+            report.converted,
+            report.series.len(),
+            report.volumes,
+            now.elapsed().as_secs_f64(),
+            report.failed,
+            report.skipped
+        )),
+        report,
+    })
+}
+
+// -- JOBS --
+
+#[tauri::command(async)]
+pub async fn create_job(source: String, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandJob> {
+    let mut registry = registry.lock().await;
+    let job = registry.create(&PathBuf::from(source)).clone();
+    registry.save()?;
+
+    Ok(CommandJob { message: None, job })
+}
+
+#[tauri::command(async)]
+pub async fn list_jobs(registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandJobs> {
+    let registry = registry.lock().await;
+    Ok(CommandJobs {
+        message: None,
+        jobs: registry.jobs(),
+    })
+}
+
+#[tauri::command(async)]
+pub async fn get_job(id: JobId, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandJob> {
+    let registry = registry.lock().await;
+    Ok(CommandJob {
+        message: None,
+        job: registry.get(id)?.clone(),
+    })
+}
 
-    // Wait for all threads to finish
-    for handle in handles {
-        match handle.await {
-            Ok(_) => {}
-            Err(e) => return Err(Error::AsyncTaskError(e.to_string())),
+// Changes the settings of a job that is not running, e.g. after reviewing its analysis or plan.
+// Settings that are not given stay unchanged, the duplicate policy is removed with `clear_duplicates`.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_job(
+    id: JobId,
+    name: Option<String>,
+    flag: Option<BundleFlag>,
+    name_template: Option<NameTemplate>,
+    volume_sizes: Option<Vec<usize>>,
+    duplicates: Option<DuplicatePolicy>,
+    clear_duplicates: Option<bool>,
+    registry: State<'_, Mutex<JobRegistry>>,
+) -> EResult<CommandJob> {
+    let mut registry = registry.lock().await;
+    let job = registry.get_mut(id)?;
+
+    if job.status == JobStatus::RUNNING {
+        return Err(Error::Unsupported(format!("Job {} is running", id)));
+    }
+    if let Some(name) = name {
+        job.name = name;
+    }
+    if let Some(flag) = flag {
+        job.flag = flag;
+    }
+    if let Some(template) = name_template {
+        // Make sure custom templates are valid before they are used for bundling
+        NameParser::new(&template)?;
+        job.name_template = template;
+    }
+    if clear_duplicates.unwrap_or(false) {
+        job.duplicates = None;
+    } else if duplicates.is_some() {
+        job.duplicates = duplicates;
+    }
+    if let Some(sizes) = volume_sizes {
+        match &mut job.plan {
+            Some(plan) => plan.volume_sizes = sizes,
+            None => return Err(Error::Unsupported(format!("Job {} is not bundled yet", id))),
         }
     }
 
-    let elapsed = now.elapsed();
+    let job = job.clone();
+    registry.save()?;
 
-    Ok(CommandDefault {
+    Ok(CommandJob { message: None, job })
+}
+
+// Emits the progress of a job as `job-progress` event to the frontend
+fn job_progress_reporter(app: AppHandle, id: JobId) -> ProgressReporter {
+    Arc::new(move |progress| {
+        let _ = app.emit("job-progress", JobProgress { job: id, progress });
+    })
+}
+
+// Runs a process of a job. The registry is only locked to start and finish the job,
+// so other jobs can be inspected and run in the meantime.
+// The options of the process are stored on start, so a failed process can be retried with them.
+async fn run_job<T, F>(
+    id: JobId,
+    process: JobProcess,
+    app: AppHandle,
+    registry: &Mutex<JobRegistry>,
+    start: impl FnOnce(&mut Job) -> EResult<()>,
+    run: impl FnOnce(Job, Series) -> F,
+    apply: impl FnOnce(&mut Job, T),
+) -> EResult<CommandJob>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let now = std::time::Instant::now();
+    let job = {
+        let mut registry = registry.lock().await;
+        let job = registry.get_mut(id)?;
+
+        if job.status == JobStatus::RUNNING {
+            return Err(Error::Unsupported(format!("Job {} is already running", id)));
+        }
+
+        start(job)?;
+        job.status = JobStatus::RUNNING;
+        job.process = Some(process);
+        job.error = None;
+        job.cancellation.reset();
+
+        let job = job.clone();
+        registry.save()?;
+        job
+    };
+
+    let series = job.series().with_progress(job_progress_reporter(app, id));
+    let result = run(job, series).await;
+
+    // The job may have been removed while it was running
+    let mut registry = registry.lock().await;
+    let job = registry.get_mut(id)?;
+    let result = match result {
+        Ok(value) => {
+            apply(job, value);
+            job.status = JobStatus::COMPLETED;
+            Ok(())
+        }
+        Err(Error::Cancelled) => {
+            job.status = JobStatus::CANCELLED;
+            Err(Error::Cancelled)
+        }
+        Err(e) => {
+            job.status = JobStatus::FAILED;
+            job.error = Some(ErrorReport::from(&e));
+            Err(e)
+        }
+    };
+
+    let job = job.clone();
+    registry.save()?;
+    result?;
+
+    Ok(CommandJob {
         message: Some(format!(
-            "Conversion completed in {:.2?} seconds.",
-            elapsed.as_secs_f64()
+            "Job {} completed in {:.2?} seconds.",
+            id,
+            now.elapsed().as_secs_f64()
         )),
+        job,
+    })
+}
+
+async fn analyze_job_process(id: JobId, app: AppHandle, registry: &Mutex<JobRegistry>) -> EResult<CommandJob> {
+    run_job(
+        id,
+        JobProcess::ANALYZE,
+        app,
+        registry,
+        |_| Ok(()),
+        |_, series| async move { palaxy_core::analyze(&series).await },
+        |job, analysis| {
+            // The recommended flag and detected naming convention are used for bundling
+            if let (BundleFlag::NAME, Some(template)) = (&analysis.flag, &analysis.template) {
+                job.name_template = template.clone();
+            }
+            job.flag = analysis.flag.clone();
+            job.analysis = Some(analysis);
+        },
+    )
+    .await
+}
+
+async fn bundle_job_process(
+    id: JobId,
+    options: Option<PlanOptions>,
+    app: AppHandle,
+    registry: &Mutex<JobRegistry>,
+) -> EResult<CommandJob> {
+    run_job(
+        id,
+        JobProcess::BUNDLE,
+        app,
+        registry,
+        |job| {
+            // The flag and duplicate policy of the job are always used, so changing them is enough to bundle differently
+            job.plan_options = Some(PlanOptions {
+                flag: job.flag.clone(),
+                duplicates: job.duplicates.clone(),
+                ..options.or_else(|| job.plan_options.clone()).unwrap_or_default()
+            });
+            Ok(())
+        },
+        |job, series| async move {
+            palaxy_core::plan(&series, &job.plan_options.unwrap_or_default()).await
+        },
+        |job, plan| job.plan = Some(plan),
+    )
+    .await
+}
+
+async fn convert_job_process(
+    id: JobId,
+    options: Option<ConvertOptions>,
+    app: AppHandle,
+    registry: &Mutex<JobRegistry>,
+) -> EResult<CommandJob> {
+    let limits = registry.lock().await.limits;
+
+    run_job(
+        id,
+        JobProcess::CONVERT,
+        app,
+        registry,
+        |job| {
+            if job.plan.is_none() {
+                return Err(Error::Unsupported(format!("Job {} is not bundled yet", id)));
+            }
+
+            job.convert_options = options
+                .or_else(|| job.convert_options.clone())
+                .map(|options| ConvertOptions { limits, ..options });
+            Ok(())
+        },
+        |job, series| async move {
+            palaxy_core::convert(
+                &series,
+                &job.plan.unwrap_or_default(),
+                &job.convert_options.unwrap_or_default(),
+            )
+            .await
+        },
+        |job, conversion| job.result = Some(conversion),
+    )
+    .await
+}
+
+#[tauri::command(async)]
+pub async fn analyze_job(id: JobId, app: AppHandle, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandJob> {
+    analyze_job_process(id, app, registry.inner()).await
+}
+
+#[tauri::command(async)]
+pub async fn bundle_job(
+    id: JobId,
+    sensibility: Option<usize>,
+    page_target: Option<usize>,
+    page_tolerance: Option<usize>,
+    detectors: Option<Vec<DetectorKind>>,
+    app: AppHandle,
+    registry: State<'_, Mutex<JobRegistry>>,
+) -> EResult<CommandJob> {
+    let options = PlanOptions {
+        sensibility,
+        page_target,
+        page_tolerance,
+        detectors,
+        ..PlanOptions::default()
+    };
+
+    bundle_job_process(id, Some(options), app, registry.inner()).await
+}
+
+#[tauri::command(async)]
+pub async fn convert_job(
+    id: JobId,
+    create_directory: bool,
+    target: String,
+    file_format: FileFormat,
+    direction: Direction,
+    app: AppHandle,
+    registry: State<'_, Mutex<JobRegistry>>,
+) -> EResult<CommandJob> {
+    let options = ConvertOptions {
+        target: PathBuf::from(target),
+        create_directory,
+        file_format,
+        direction,
+        limits: ConvertLimits::default(),
+    };
+
+    convert_job_process(id, Some(options), app, registry.inner()).await
+}
+
+// Repeats the last process of a job with the options it was started with
+#[tauri::command(async)]
+pub async fn retry_job(id: JobId, app: AppHandle, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandJob> {
+    let process = registry.lock().await.get(id)?.process;
+
+    match process {
+        Some(JobProcess::ANALYZE) => analyze_job_process(id, app, registry.inner()).await,
+        Some(JobProcess::BUNDLE) => bundle_job_process(id, None, app, registry.inner()).await,
+        Some(JobProcess::CONVERT) => convert_job_process(id, None, app, registry.inner()).await,
+        None => Err(Error::Unsupported(format!("Job {} has not been started yet", id))),
+    }
+}
+
+#[tauri::command(async)]
+pub async fn cancel_job(id: JobId, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandDefault> {
+    let registry = registry.lock().await;
+    registry.get(id)?.cancellation.cancel();
+
+    Ok(CommandDefault {
+        message: Some(format!("Cancelling job {}.", id)),
     })
 }
+
+// Running jobs are cancelled before they are removed
+#[tauri::command(async)]
+pub async fn remove_job(id: JobId, registry: State<'_, Mutex<JobRegistry>>) -> EResult<CommandDefault> {
+    let mut registry = registry.lock().await;
+    registry.remove(id)?.cancellation.cancel();
+    registry.save()?;
+
+    Ok(CommandDefault::default())
+}
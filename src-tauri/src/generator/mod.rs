@@ -1,3 +0,0 @@
-pub mod cbz;
-pub mod epub;
-pub mod pdf;
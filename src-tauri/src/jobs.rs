@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use palaxy_core::naming::NameTemplate;
+use palaxy_core::scheduler::ConvertLimits;
+use palaxy_core::{Analysis, Conversion, ConvertOptions, DuplicatePolicy, Plan, PlanOptions, Series};
+
+use crate::prelude::*;
+
+pub type JobId = u64;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum JobStatus {
+    PENDING,
+    RUNNING,
+    // The last process of the job succeeded
+    COMPLETED,
+    FAILED,
+    CANCELLED,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum JobProcess {
+    ANALYZE,
+    BUNDLE,
+    CONVERT,
+}
+
+// A single series with its own plan, which is prepared and converted independently of all other jobs
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Job {
+    pub id: JobId,
+    pub source: PathBuf,
+    pub name: String,
+    pub name_template: NameTemplate,
+    pub flag: BundleFlag,
+    // Jobs saved before duplicate policies existed bundle all chapters
+    #[serde(default)]
+    pub duplicates: Option<DuplicatePolicy>,
+    pub status: JobStatus,
+    // The last started process, which is repeated with the same options on retry
+    pub process: Option<JobProcess>,
+    pub analysis: Option<Analysis>,
+    pub plan_options: Option<PlanOptions>,
+    pub plan: Option<Plan>,
+    pub convert_options: Option<ConvertOptions>,
+    pub result: Option<Conversion>,
+    pub error: Option<ErrorReport>,
+    #[serde(skip)]
+    pub cancellation: CancellationToken,
+}
+
+impl Job {
+    pub fn series(&self) -> Series {
+        Series::new(&self.source)
+            .with_name(&self.name)
+            .with_name_template(self.name_template.clone())
+            .with_cancellation(self.cancellation.clone())
+    }
+}
+
+// Payload of the `job-progress` event
+#[derive(Serialize, Clone)]
+pub struct JobProgress {
+    pub job: JobId,
+    pub progress: Progress,
+}
+
+// All jobs, which are written to disk after every change so they survive a restart of the app
+#[derive(Serialize, Deserialize, Default)]
+pub struct JobRegistry {
+    next_id: JobId,
+    jobs: BTreeMap<JobId, Job>,
+    // Used for every conversion of a job
+    pub limits: ConvertLimits,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl JobRegistry {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        }
+    }
+
+    // Jobs that were still running when the app was closed are marked as failed, so they can be retried
+    pub fn load(path: &Path) -> EResult<Self> {
+        if !path.exists() {
+            return Ok(Self::new(path));
+        }
+
+        let mut registry: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        registry.path = Some(path.to_path_buf());
+
+        for job in registry.jobs.values_mut() {
+            if job.status == JobStatus::RUNNING {
+                job.status = JobStatus::FAILED;
+                job.error = Some(ErrorReport::from(&Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "The process was interrupted by closing the app",
+                ))));
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn save(&self) -> EResult<()> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(path, serde_json::to_string(self)?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn create(&mut self, source: &Path) -> &Job {
+        let series = Series::new(source);
+
+        self.next_id += 1;
+        self.jobs.entry(self.next_id).or_insert(Job {
+            id: self.next_id,
+            source: series.source,
+            name: series.name,
+            name_template: series.name_template,
+            flag: BundleFlag::default(),
+            duplicates: None,
+            status: JobStatus::PENDING,
+            process: None,
+            analysis: None,
+            plan_options: None,
+            plan: None,
+            convert_options: None,
+            result: None,
+            error: None,
+            cancellation: CancellationToken::default(),
+        })
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        self.jobs.values().cloned().collect()
+    }
+
+    pub fn get(&self, id: JobId) -> EResult<&Job> {
+        self.jobs
+            .get(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job {}", id)))
+    }
+
+    pub fn get_mut(&mut self, id: JobId) -> EResult<&mut Job> {
+        self.jobs
+            .get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job {}", id)))
+    }
+
+    pub fn remove(&mut self, id: JobId) -> EResult<Job> {
+        self.jobs
+            .remove(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job {}", id)))
+    }
+}
@@ -5,9 +5,8 @@ use crate::commands::*;
 use tauri::{Builder, Manager};
 use tokio::sync::Mutex;
 
-mod collector;
 mod commands;
-mod generator;
+mod jobs;
 mod prelude;
 
 fn main() {
@@ -15,22 +14,61 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             app.manage(Mutex::new(prelude::AppState::default()));
+            // Kept outside of the app state, as running processes hold its lock until they finish
+            app.manage(prelude::CancellationToken::default());
+            // A broken job file must not prevent the app from starting, it is replaced on the next change
+            let jobs = app.path().app_data_dir()?.join("jobs.json");
+            app.manage(Mutex::new(
+                jobs::JobRegistry::load(&jobs).unwrap_or_else(|_| jobs::JobRegistry::new(&jobs)),
+            ));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // setter
             set_source,
             set_bundle_flag,
+            set_name_template,
             set_data,
             set_volume_sizes,
+            set_convert_limits,
+            set_duplicate_policy,
+            set_library_entry,
             // getter
             get_data,
+            get_library,
             // reset
             reset,
+            cancel,
             // processes
             analyze,
+            apply_fix,
             bundle,
+            validate_plan,
             convert,
+            scan_library,
+            bundle_library,
+            convert_library,
+            // plan file
+            export_plan,
+            import_plan,
+            // rename
+            preview_renames,
+            apply_renames,
+            undo_renames,
+            // project
+            save_project,
+            load_project,
+            // jobs
+            create_job,
+            list_jobs,
+            get_job,
+            update_job,
+            analyze_job,
+            bundle_job,
+            convert_job,
+            retry_job,
+            cancel_job,
+            remove_job,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,46 +1,13 @@
-use printpdf::image_crate;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-// Error types
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
-    #[error(transparent)]
-    Regex(#[from] regex::Error),
-    #[error(transparent)]
-    Tauri(#[from] tauri::Error),
-    #[error(transparent)]
-    Image(#[from] image::ImageError),
-    #[error(transparent)]
-    Epub(#[from] eyre::Report),
-    #[error(transparent)]
-    Zip(#[from] zip::result::ZipError),
-    #[error(transparent)]
-    PrintPdf(#[from] printpdf::Error),
-    #[error(transparent)]
-    PrintPdfImage(#[from] image_crate::error::ImageError),
-    #[error("The given path '{0}' is invalid: {1}")]
-    InvalidPath(PathBuf, String),
-    #[error("Asynchronous task failed: {0}")]
-    AsyncTaskError(String),
-    #[error("Unsupported: {0}")]
-    Unsupported(String),
-    #[error("Not found: {0}")]
-    NotFound(String),
-}
-
-impl serde::Serialize for Error {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::ser::Serializer,
-    {
-        serializer.serialize_str(self.to_string().as_ref())
-    }
-}
+use palaxy_core::naming::NameTemplate;
+use palaxy_core::scheduler::ConvertLimits;
+use crate::jobs::Job;
+use palaxy_core::project::ProjectSource;
+use palaxy_core::{Diagnostic, DuplicateChoice, DuplicatePolicy, Library, LibraryEntry, LibraryReport, Plan, PlanIssue, Project, RenamePlan, Series};
 
-pub type EResult<T> = Result<T, Error>;
+pub use palaxy_core::prelude::*;
 
 // App state
 #[derive(Serialize, Deserialize, Clone, Default)]
@@ -48,16 +15,43 @@ pub struct AppState {
     pub name: String,
     pub source: PathBuf,
     pub bundle_flag: BundleFlag,
+    pub name_template: NameTemplate,
+    // Decides which of several chapters with the same number is bundled
+    pub duplicates: Option<DuplicatePolicy>,
     pub volume_sizes: Vec<usize>,
     pub data: Vec<Vec<PathBuf>>,
+    // Kept on reset, as the limits depend on the machine instead of the source
+    pub convert_limits: ConvertLimits,
+    // The series of a library root, which are converted in a batch instead of the single source
+    pub library: Library,
 }
 
 impl AppState {
     pub fn reset(&mut self) {
         self.source = PathBuf::default();
         self.bundle_flag = BundleFlag::default();
+        self.name_template = NameTemplate::default();
+        self.duplicates = None;
         self.volume_sizes = Vec::default();
         self.data = Vec::default();
+        self.library = Library::default();
+    }
+
+    // The series of the source with the name and naming convention set by the frontend
+    pub fn series(&self) -> Series {
+        Series::new(&self.source)
+            .with_name(&self.name)
+            .with_name_template(self.name_template.clone())
+    }
+
+    // The plan of the volumes as currently edited by the frontend
+    pub fn plan(&self) -> Plan {
+        Plan {
+            pages: self.data.clone(),
+            volume_sizes: self.volume_sizes.clone(),
+            covers: Vec::new(),
+            duplicates: Vec::new(),
+        }
     }
 }
 
@@ -78,39 +72,37 @@ pub struct CommandBundle {
     pub total_chapters: usize,
     pub total_volumes: Option<usize>,
     pub chapter_sizes: Option<Vec<usize>>,
+    pub covers: Option<Vec<CoverScore>>,
+    // The chapters with the same number and which of them were bundled
+    pub duplicates: Option<Vec<DuplicateChoice>>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CommandAnalyze {
     pub message: Option<String>,
-    pub negative: Vec<String>,
-    pub positive: Vec<String>,
-    pub suggest: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
     pub flag: BundleFlag,
+    pub template: Option<NameTemplate>,
 }
 
-// Types shared between frontend and tauri
-#[derive(Serialize, Deserialize, Clone, Copy, Default)]
-pub enum FileFormat {
-    PDF,
-    EPUB,
-    #[default]
-    CBZ,
+#[derive(Serialize, Deserialize)]
+pub struct AnalyzeResult {
+    pub message: Option<String>,
+    pub chapter_per_volume: Vec<usize>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
-pub enum Direction {
-    #[default]
-    #[serde(rename = "Left to Right")]
-    LTR,
-    #[serde(rename = "Right to Left")]
-    RTL,
+#[derive(Serialize, Deserialize)]
+pub struct CommandConvert {
+    pub message: Option<String>,
+    pub failed: usize,
+    pub volumes: Vec<VolumeReport>,
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct AnalyzeResult {
+pub struct CommandValidatePlan {
     pub message: Option<String>,
-    pub chapter_per_volume: Vec<usize>,
+    pub valid: bool,
+    pub issues: Vec<PlanIssue>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -118,23 +110,42 @@ pub struct ConvertResult {
     pub message: Option<String>,
 }
 
-// Workflow types
-#[derive(Serialize, Deserialize, PartialEq, Clone, Default)]
-pub enum BundleFlag {
-    NAME,
-    IMAGE,
-    #[default]
-    MANUAL,
+#[derive(Serialize, Deserialize)]
+pub struct CommandLibrary {
+    pub message: Option<String>,
+    pub root: PathBuf,
+    pub entries: Vec<LibraryEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommandConvertLibrary {
+    pub message: Option<String>,
+    pub report: LibraryReport,
 }
 
-// Utils
-pub fn get_file_info(image_path: &PathBuf) -> Result<(&'static str, &'static str), Error> {
-    let path = image_path.extension().and_then(|e| e.to_str());
+#[derive(Serialize, Deserialize)]
+pub struct CommandJob {
+    pub message: Option<String>,
+    pub job: Job,
+}
 
-    match path {
-        Some("jpg") | Some("jpeg") => Ok(("jpg", "image/jpeg")),
-        Some("png") => Ok(("png", "image/png")),
-        Some("webp") => Ok(("webp", "image/webp")),
-        _ => Err(Error::Unsupported(format!("Image format {:#?}", path))),
-    }
+#[derive(Serialize, Deserialize)]
+pub struct CommandJobs {
+    pub message: Option<String>,
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommandRenames {
+    pub message: Option<String>,
+    pub renames: RenamePlan,
+    // One line per rename, relative to the source
+    pub diff: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommandProject {
+    pub message: Option<String>,
+    pub project: Project,
+    pub source: ProjectSource,
 }
@@ -1,26 +1,297 @@
-import type { BaseResult } from "$lib/types"
+import type { BaseResult, ErrorReport } from "$lib/types"
 
 export enum BundleFlag {
 	NAME = "NAME",
 	IMAGE = "IMAGE",
+	FOLDER = "FOLDER",
+	PAGES = "PAGES",
 	MANUAL = "MANUAL",
 }
 
+// Either one of the preset naming conventions or a custom regex
+type NameTemplate = "DASHED" | "LABELED" | "COMPACT" | "MIHON" | { CUSTOM: string }
+
+type Severity = "INFO" | "WARNING" | "ERROR"
+
+interface Rename {
+	from: string
+	to: string
+}
+
+// A change to the source that solves a diagnostic, passed back unchanged to `apply_fix`
+type Fix = { RENAME: Array<Rename> } | { DELETE: Array<string> }
+
+// A page a diagnostic refers to and its chapter
+interface PageReference {
+	chapter: string
+	page: string | null
+	number: number | null
+}
+
+// A single finding of the analysis and all paths it applies to
+interface Diagnostic {
+	code:
+		| "NO_CHAPTERS"
+		| "EMPTY_CHAPTERS"
+		| "STRAY_FILES"
+		| "READ_ONLY"
+		| "NESTED_LAYOUT"
+		| "NAMING_DETECTED"
+		| "NON_DASHED_NAMING"
+		| "UNKNOWN_NAMING"
+		| "DIR_LACKS_NUMBER"
+		| "FILE_LACKS_NUMBER"
+		| "FALLBACK_BUNDLING"
+		| "MISSING_CHAPTERS"
+		| "DUPLICATE_CHAPTERS"
+		| "AMBIGUOUS_DECIMALS"
+		| "VOLUME_REGRESSION"
+		| "MISSING_PAGES"
+		| "DUPLICATE_PAGES"
+		| "EMPTY_PAGES"
+	severity: Severity
+	message: string
+	paths: Array<string>
+	// Only given by checks of single pages, missing pages have no path
+	pages: Array<PageReference>
+	fix: Fix | null
+}
+
 interface CommandAnalyze extends BaseResult {
-	positive: Array<string>
-	negative: Array<string>
-	suggest: Array<string>
+	diagnostics: Array<Diagnostic>
 	flag: BundleFlag
+	template: NameTemplate | null
 }
 
 interface CommandBundle extends BaseResult {
 	total_chapters: number
 	total_volumes: number | null
 	chapter_sizes: Array<number> | null
+	covers: Array<CoverScore> | null
+	duplicates: Array<DuplicateChoice> | null
+}
+
+// Decides which of several chapters with the same number is bundled, set with `set_duplicate_policy`
+type DuplicatePolicy = { GROUPS: Array<string> } | "PAGES" | "RESOLUTION" | { MANUAL: Array<string> }
+
+// Chapters with the same number, all candidates are kept without a choice
+interface DuplicateChoice {
+	volume: number | null
+	chapter: number
+	candidates: Array<string>
+	chosen: string | null
+	rejected: Array<string>
+}
+
+// The cover detection result of a single chapter
+interface CoverScore {
+	chapter: number
+	cover: string
+	colorfulness: number
+	score: number
+	confidence: number
+	volume_start: boolean
+	detectors: Array<{ detector: DetectorKind; score: number }>
+}
+
+// The strategies used to detect volume boundaries
+type DetectorKind = "COLOR" | "BLEED" | "SIZE" | "JUMP" | "BLANK"
+
+interface CommandConvert extends BaseResult {
+	failed: number
+	volumes: Array<VolumeReport>
+}
+
+// The result of the conversion of a single volume
+interface VolumeReport {
+	volume: number
+	name: string
+	success: boolean
+	path: string | null
+	size: number | null
+	error: ErrorReport | null
+}
+
+// Payload of the `progress` event, emitted while analyzing, bundling and converting
+interface Progress {
+	stage: "COLLECT" | "DETECT" | "CONVERT"
+	series: number | null
+	volume: number | null
+	index: number
+	total: number
+	file: string | null
+}
+
+// A series inside of a library root, bundled with its own options
+interface LibraryEntry {
+	source: string
+	name: string
+	name_template: NameTemplate
+	options: PlanOptions | null
+	skip: boolean
+	analysis: Omit<CommandAnalyze, "message"> | null
+	plan: {
+		pages: Array<Array<string>>
+		volume_sizes: Array<number>
+		covers: Array<CoverScore>
+		duplicates: Array<DuplicateChoice>
+	} | null
+	error: ErrorReport | null
+}
+
+interface PlanOptions {
+	flag: BundleFlag
+	sensibility: number | null
+	page_target: number | null
+	page_tolerance: number | null
+	detectors: Array<DetectorKind> | null
+	sizes: Array<number> | null
+	duplicates: DuplicatePolicy | null
+}
+
+interface CommandLibrary extends BaseResult {
+	root: string
+	entries: Array<LibraryEntry>
+}
+
+// The aggregate result of converting a library
+interface CommandConvertLibrary extends BaseResult {
+	report: {
+		converted: number
+		failed: number
+		skipped: number
+		volumes: number
+		series: Array<{
+			name: string
+			source: string
+			skipped: boolean
+			conversion: Omit<CommandConvert, "message"> | null
+			error: ErrorReport | null
+		}>
+	}
+}
+
+// A single series that is prepared and converted independently of the others
+interface Job {
+	id: number
+	source: string
+	name: string
+	name_template: NameTemplate
+	flag: BundleFlag
+	duplicates: DuplicatePolicy | null
+	status: "PENDING" | "RUNNING" | "COMPLETED" | "FAILED" | "CANCELLED"
+	process: "ANALYZE" | "BUNDLE" | "CONVERT" | null
+	analysis: Omit<CommandAnalyze, "message"> | null
+	plan_options: PlanOptions | null
+	plan: {
+		pages: Array<Array<string>>
+		volume_sizes: Array<number>
+		covers: Array<CoverScore>
+		duplicates: Array<DuplicateChoice>
+	} | null
+	convert_options: Record<string, unknown> | null
+	result: Omit<CommandConvert, "message"> | null
+	error: ErrorReport | null
+}
+
+interface CommandJob extends BaseResult {
+	job: Job
+}
+
+interface CommandJobs extends BaseResult {
+	jobs: Array<Job>
+}
+
+// Payload of the `job-progress` event
+interface JobProgress {
+	job: number
+	progress: Progress
+}
+
+// A saved series and plan, the pages are relative to the source
+interface Project {
+	version: number
+	palaxy: string
+	saved: number
+	source: string
+	name: string
+	name_template: NameTemplate
+	flag: BundleFlag
+	plan: {
+		pages: Array<Array<string>>
+		volume_sizes: Array<number>
+		covers: Array<CoverScore>
+		duplicates: Array<DuplicateChoice>
+	}
+	duplicates: DuplicatePolicy | null
+	convert: Record<string, unknown> | null
+	// Projects of version 1 have no limits
+	limits: { volumes: number | null; pages: number | null; memory: number | null } | null
+}
+
+interface CommandRenames extends BaseResult {
+	renames: {
+		source: string
+		renames: Array<Rename>
+	}
+	diff: string
+}
+
+interface CommandProject extends BaseResult {
+	project: Project
+	source: {
+		source: string
+		relocated: string | null
+		missing_pages: number
+	}
+}
+
+// A problem that prevents the plan from being converted, volumes and chapters are counted from 1
+interface PlanIssue {
+	kind: "NO_VOLUMES" | "SIZE_MISMATCH" | "EMPTY_VOLUME" | "EMPTY_CHAPTER" | "MISSING_FILE" | "UNSUPPORTED_FORMAT"
+	volume: number | null
+	chapter: number | null
+	file: string | null
+	message: string
+}
+
+interface CommandValidatePlan extends BaseResult {
+	valid: boolean
+	issues: Array<PlanIssue>
 }
 
 interface CommandGetData extends BaseResult {
 	data: Array<Array<string>>
 }
 
-export type { CommandAnalyze, CommandBundle, CommandGetData }
+export type {
+	CommandAnalyze,
+	CommandBundle,
+	CommandConvert,
+	CommandConvertLibrary,
+	CommandGetData,
+	CommandJob,
+	CommandJobs,
+	CommandLibrary,
+	CommandProject,
+	CommandRenames,
+	CommandValidatePlan,
+	CoverScore,
+	DetectorKind,
+	Diagnostic,
+	DuplicateChoice,
+	DuplicatePolicy,
+	Fix,
+	Job,
+	JobProgress,
+	LibraryEntry,
+	NameTemplate,
+	PageReference,
+	PlanIssue,
+	PlanOptions,
+	Project,
+	Progress,
+	Rename,
+	Severity,
+	VolumeReport,
+}
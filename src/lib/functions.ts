@@ -4,7 +4,7 @@ import {
 	setModeUserPrefers,
 	type ToastSettings,
 } from "@skeletonlabs/skeleton"
-import { type BaseResult, InfoType, Theme, type Toast } from "$lib/types"
+import { type BaseResult, type ErrorReport, InfoType, Theme, type Toast } from "$lib/types"
 import { toast } from "$lib/stores"
 import { invoke } from "@tauri-apps/api/core"
 import { tabIndex } from "$components/converter"
@@ -91,10 +91,12 @@ export async function bridge<R extends BaseResult>(
 
 		return result
 	} catch (e) {
+		const error = e as ErrorReport
 		if (get(tabIndex) > 0) tabIndex.update(n => n - 1)
 		toast.set({
-			type: InfoType.ERROR,
-			message: e as string,
+			// A cancelled process was stopped on purpose
+			type: error.category === "CANCELLED" ? InfoType.WARNING : InfoType.ERROR,
+			message: error.message ?? String(e),
 			timeout: 5000,
 		} as Toast)
 	}
@@ -14,6 +14,16 @@ export interface BaseResult {
 	message: string | null
 }
 
+// Errors of the backend, the code is stable and can be used to react to specific errors
+export interface ErrorReport {
+	code: string
+	category: "FILESYSTEM" | "INPUT" | "IMAGE" | "ARCHIVE" | "OUTPUT" | "PROCESS" | "CANCELLED"
+	message: string
+	path: string | null
+	chapter: number | null
+	volume: number | null
+}
+
 // Frontend-only types
 export enum InfoType {
 	ERROR,